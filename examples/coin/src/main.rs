@@ -3,7 +3,7 @@ use brainwash::*;
 fn main() {
     let bpm = 240.;
     let mut clock = Clock::default();
-    clock.bpm(bpm).bars(1.);
+    clock.bpm(bpm).beats(4.);
     let freq_env = Envelope::new(vec![
         point(0., 0.4),
         point(0.03, 0.4),
@@ -6,7 +6,7 @@ fn main() {
     let bpm = 100.;
     let base_bars = 4. * 4.;
     let mut clock = Clock::default();
-    clock.bpm(bpm).bars(base_bars);
+    clock.bpm(bpm).beats(base_bars * 4.);
     let scale = cmin();
     let mut reverb = Reverb::default();
     let mut lpf1 = LowpassFilter::default();
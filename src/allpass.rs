@@ -33,6 +33,10 @@ impl AllpassFilter {
         self
     }
 
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
     pub fn copy_state_from(&mut self, other: &AllpassFilter) {
         let copy_len = self.buffer_size.min(other.buffer_size);
         for i in 0..copy_len {
@@ -0,0 +1,191 @@
+use crate::{filters::LowpassFilter, signal::Signal, utils::next_random};
+
+pub struct Bitcrusher {
+    bits: f32,
+    rate: f32,
+    jitter: f32,
+    dither: bool,
+    anti_alias: bool,
+    drive: f32,
+    mix: f32,
+    pre_filter: LowpassFilter,
+    hold_remaining: f32,
+    held: f32,
+    seed: u32,
+}
+
+impl Default for Bitcrusher {
+    fn default() -> Self {
+        Self {
+            bits: 8.0,
+            rate: 1.0,
+            jitter: 0.0,
+            dither: false,
+            anti_alias: false,
+            drive: 1.0,
+            mix: 1.0,
+            pre_filter: LowpassFilter::default(),
+            hold_remaining: 0.0,
+            held: 0.0,
+            seed: 22222,
+        }
+    }
+}
+
+impl Bitcrusher {
+    pub fn bits(&mut self, bits: f32) -> &mut Self {
+        self.bits = bits.clamp(1.0, 24.0);
+        self
+    }
+
+    pub fn rate(&mut self, factor: f32) -> &mut Self {
+        self.rate = factor.clamp(1.0, 256.0);
+        self
+    }
+
+    pub fn jitter(&mut self, amount: f32) -> &mut Self {
+        self.jitter = amount.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn dither(&mut self, enabled: bool) -> &mut Self {
+        self.dither = enabled;
+        self
+    }
+
+    pub fn anti_alias(&mut self, enabled: bool) -> &mut Self {
+        self.anti_alias = enabled;
+        self
+    }
+
+    pub fn drive(&mut self, amount: f32) -> &mut Self {
+        self.drive = amount.clamp(0.1, 100.0);
+        self
+    }
+
+    pub fn mix(&mut self, amount: f32) -> &mut Self {
+        self.mix = amount.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn seed(&mut self, seed: u32) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+
+    fn random(&mut self) -> f32 {
+        next_random(&mut self.seed)
+    }
+
+    fn quantize(&mut self, x: f32) -> f32 {
+        let levels = 2.0f32.powf(self.bits - 1.0);
+        let dithered = if self.dither {
+            x + (self.random() - self.random()) / levels
+        } else {
+            x
+        };
+        ((dithered.clamp(-1.0, 1.0) * levels).round() / levels).clamp(-1.0, 1.0)
+    }
+
+    pub fn output(&mut self, input: f32, signal: &mut Signal) -> f32 {
+        let driven = input * self.drive;
+        let filtered = if self.anti_alias && self.rate > 1.0 {
+            self.pre_filter.output(driven, 0.9 / self.rate, signal)
+        } else {
+            driven
+        };
+
+        if self.hold_remaining <= 0.0 {
+            let spread = (self.random() * 2.0 - 1.0) * self.jitter * 0.5;
+            self.hold_remaining += (self.rate * (1.0 + spread)).max(1.0);
+            self.held = self.quantize(filtered);
+        }
+        self.hold_remaining -= 1.0;
+
+        input * (1.0 - self.mix) + self.held * self.mix
+    }
+
+    pub fn reset(&mut self) {
+        self.hold_remaining = 0.0;
+        self.held = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &Bitcrusher) {
+        self.pre_filter.copy_state_from(&other.pre_filter);
+        self.hold_remaining = other.hold_remaining;
+        self.held = other.held;
+        self.seed = other.seed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_depth_quantizes() {
+        let mut crusher = Bitcrusher::default();
+        crusher.bits(2.0);
+        let mut signal = Signal::new(44100);
+
+        for i in -100..=100 {
+            let out = crusher.output(i as f32 * 0.01, &mut signal);
+            let steps = out * 2.0;
+            assert!(
+                (steps - steps.round()).abs() < 1e-6,
+                "{} is not on a 2-bit level",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn test_rate_holds_samples() {
+        let mut crusher = Bitcrusher::default();
+        crusher.bits(24.0).rate(4.0);
+        let mut signal = Signal::new(44100);
+
+        let outputs: Vec<f32> = (0..8)
+            .map(|i| crusher.output(i as f32 * 0.1, &mut signal))
+            .collect();
+        assert_eq!(outputs[0], outputs[3]);
+        assert_ne!(outputs[3], outputs[4]);
+        assert_eq!(outputs[4], outputs[7]);
+    }
+
+    #[test]
+    fn test_dry_mix_passes_input() {
+        let mut crusher = Bitcrusher::default();
+        crusher
+            .bits(1.0)
+            .rate(16.0)
+            .jitter(1.0)
+            .dither(true)
+            .mix(0.0);
+        let mut signal = Signal::new(44100);
+
+        for i in 0..100 {
+            let input = (i as f32 * 0.1).sin();
+            assert_eq!(crusher.output(input, &mut signal), input);
+        }
+    }
+
+    #[test]
+    fn test_jitter_and_anti_alias_stay_bounded() {
+        let mut crusher = Bitcrusher::default();
+        crusher
+            .bits(6.0)
+            .rate(8.0)
+            .jitter(1.0)
+            .dither(true)
+            .anti_alias(true)
+            .drive(4.0);
+        let mut signal = Signal::new(44100);
+
+        for i in 0..4410 {
+            let input = (i as f32 * 0.3).sin();
+            let out = crusher.output(input, &mut signal);
+            assert!(out.is_finite() && out.abs() <= 1.5, "output {}", out);
+        }
+    }
+}
@@ -2,7 +2,8 @@ use crate::{Osc, Signal};
 
 pub struct Clock {
     bpm: f32,
-    bars: f32,
+    // Length of a loop in quarter notes.
+    beats: f32,
     osc: Osc,
 }
 
@@ -10,7 +11,7 @@ impl Default for Clock {
     fn default() -> Self {
         Self {
             bpm: 100.,
-            bars: 1.,
+            beats: 4.,
             osc: Osc::default(),
         }
     }
@@ -22,17 +23,28 @@ impl Clock {
         self
     }
 
-    pub fn bars(&mut self, bars: f32) -> &mut Self {
-        self.bars = bars;
+    /// Sets the loop length in quarter notes, e.g. `Track::beats`.
+    pub fn beats(&mut self, beats: f32) -> &mut Self {
+        self.beats = beats.max(f32::EPSILON);
         self
     }
 
+    /// Sets the loop length in bars of 4/4.
+    pub fn bars(&mut self, bars: f32) -> &mut Self {
+        self.beats(bars * 4.0)
+    }
+
     pub fn current_bpm(&self) -> f32 {
         self.bpm
     }
 
+    pub fn current_beats(&self) -> f32 {
+        self.beats
+    }
+
+    /// The loop length in bars of 4/4.
     pub fn current_bars(&self) -> f32 {
-        self.bars
+        self.beats / 4.0
     }
 
     pub fn phase(&self) -> f32 {
@@ -42,8 +54,7 @@ impl Clock {
     pub fn output(&mut self, signal: &mut Signal) -> f32 {
         let beats_per_minute = self.bpm;
         let beats_per_second = beats_per_minute / 60.0;
-        let bars_per_second = beats_per_second / 4.0;
-        let frequency = bars_per_second / self.bars;
+        let frequency = beats_per_second / self.beats;
 
         self.osc.saw().unipolar().freq(frequency).output(signal)
     }
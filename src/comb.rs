@@ -1,3 +1,5 @@
+use crate::utils::flush_denormal;
+
 pub struct CombFilter {
     buffer: Vec<f32>,
     buffer_size: usize,
@@ -22,11 +24,9 @@ impl CombFilter {
     }
 
     pub fn output(&mut self, input: f32) -> f32 {
-        let mut output = self.buffer[self.buffer_index];
-        undenormalise(&mut output);
+        let output = flush_denormal(self.buffer[self.buffer_index]);
 
-        self.filterstore = (output * self.damp2) + (self.filterstore * self.damp1);
-        undenormalise(&mut self.filterstore);
+        self.filterstore = flush_denormal((output * self.damp2) + (self.filterstore * self.damp1));
 
         self.buffer[self.buffer_index] = input + (self.filterstore * self.feedback);
 
@@ -49,6 +49,11 @@ impl CombFilter {
         self
     }
 
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.filterstore = 0.0;
+    }
+
     pub fn copy_state_from(&mut self, other: &CombFilter) {
         let copy_len = self.buffer_size.min(other.buffer_size);
         for i in 0..copy_len {
@@ -58,10 +63,3 @@ impl CombFilter {
         self.filterstore = other.filterstore;
     }
 }
-
-fn undenormalise(sample: &mut f32) {
-    const DENORMAL_THRESHOLD: f32 = 1e-15;
-    if sample.abs() < DENORMAL_THRESHOLD {
-        *sample = 0.0;
-    }
-}
@@ -43,7 +43,7 @@ impl Delay {
         let delay_frac = delay_samples.fract();
 
         let read_index = (self.write_index + self.buffer_size - delay_int) % self.buffer_size;
-        let next_index = (read_index + 1) % self.buffer_size;
+        let next_index = (read_index + self.buffer_size - 1) % self.buffer_size;
 
         let delayed_sample = self.buffer[read_index];
         let next_sample = self.buffer[next_index];
@@ -64,6 +64,10 @@ impl Delay {
         }
     }
 
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
     pub fn copy_state_from(&mut self, other: &Delay) {
         let copy_len = self.buffer_size.min(other.buffer_size);
         for i in 0..copy_len {
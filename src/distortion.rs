@@ -1,3 +1,5 @@
+use crate::oversampler::{OversampleFactor, Oversampler};
+
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub enum DistortionType {
     #[default]
@@ -8,113 +10,16 @@ pub enum DistortionType {
     Clip,
 }
 
-struct AllpassSection {
-    a: f64,
-    x1: f64,
-    y1: f64,
-}
-
-impl AllpassSection {
-    fn new(coefficient: f64) -> Self {
-        Self {
-            a: coefficient,
-            x1: 0.0,
-            y1: 0.0,
-        }
-    }
-
-    fn process(&mut self, input: f64) -> f64 {
-        let y = self.x1 + (input - self.y1) * self.a;
-        self.x1 = input;
-        self.y1 = y;
-        y
-    }
-
-    fn reset(&mut self) {
-        self.x1 = 0.0;
-        self.y1 = 0.0;
-    }
-}
-
-struct HalfbandFilter {
-    even: Vec<AllpassSection>,
-    odd: Vec<AllpassSection>,
-    prev_sample: f64,
-}
-
-impl HalfbandFilter {
-    fn new(coeffs: &[f64]) -> Self {
-        let even: Vec<_> = coeffs
-            .iter()
-            .step_by(2)
-            .map(|&c| AllpassSection::new(c))
-            .collect();
-        let odd: Vec<_> = coeffs
-            .iter()
-            .skip(1)
-            .step_by(2)
-            .map(|&c| AllpassSection::new(c))
-            .collect();
-        Self {
-            even,
-            odd,
-            prev_sample: 0.0,
-        }
-    }
-
-    fn upsample(&mut self, input: f64) -> (f64, f64) {
-        let mut even_out = input;
-        for section in &mut self.even {
-            even_out = section.process(even_out);
-        }
-
-        let mut odd_out = self.prev_sample;
-        for section in &mut self.odd {
-            odd_out = section.process(odd_out);
-        }
-        self.prev_sample = input;
-
-        ((even_out + odd_out) * 0.5, (even_out - odd_out) * 0.5)
-    }
-
-    fn downsample(&mut self, in0: f64, in1: f64) -> f64 {
-        let mut even_out = in0;
-        for section in &mut self.even {
-            even_out = section.process(even_out);
-        }
-
-        let mut odd_out = in1;
-        for section in &mut self.odd {
-            odd_out = section.process(odd_out);
-        }
-
-        (even_out + odd_out) * 0.5
-    }
-
-    fn reset(&mut self) {
-        for s in &mut self.even {
-            s.reset();
-        }
-        for s in &mut self.odd {
-            s.reset();
-        }
-        self.prev_sample = 0.0;
-    }
-}
-
-const HIIR_8: [f64; 4] = [
-    0.07711507983241622,
-    0.22823651466538192,
-    0.421_978_042_944_980_2,
-    0.690_592_758_682_615_8,
-];
-
-pub struct Distortion {
+#[derive(Clone, Copy)]
+struct Shaper {
     drive: f32,
     asymmetry: f32,
     dist_type: DistortionType,
-    up_filter: HalfbandFilter,
-    down_filter: HalfbandFilter,
+}
+
+pub struct Distortion {
+    shaper: Shaper,
+    oversampler: Oversampler,
     dc_blocker_x1: f32,
     dc_blocker_y1: f32,
 }
@@ -122,11 +27,12 @@ pub struct Distortion {
 impl Default for Distortion {
     fn default() -> Self {
         Self {
-            drive: 1.0,
-            asymmetry: 0.0,
-            dist_type: DistortionType::Tube,
-            up_filter: HalfbandFilter::new(&HIIR_8),
-            down_filter: HalfbandFilter::new(&HIIR_8),
+            shaper: Shaper {
+                drive: 1.0,
+                asymmetry: 0.0,
+                dist_type: DistortionType::Tube,
+            },
+            oversampler: Oversampler::default(),
             dc_blocker_x1: 0.0,
             dc_blocker_y1: 0.0,
         }
@@ -152,20 +58,53 @@ fn soft_clip_cubic(x: f32) -> f32 {
 
 impl Distortion {
     pub fn drive(&mut self, amount: f32) -> &mut Self {
-        self.drive = amount.clamp(0.1, 100.0);
+        self.shaper.drive = amount.clamp(0.1, 100.0);
         self
     }
 
     pub fn asymmetry(&mut self, amount: f32) -> &mut Self {
-        self.asymmetry = amount.clamp(-1.0, 1.0);
+        self.shaper.asymmetry = amount.clamp(-1.0, 1.0);
         self
     }
 
     pub fn dist_type(&mut self, t: DistortionType) -> &mut Self {
-        self.dist_type = t;
+        self.shaper.dist_type = t;
         self
     }
 
+    pub fn oversample(&mut self, factor: OversampleFactor) -> &mut Self {
+        self.oversampler.factor(factor);
+        self
+    }
+
+    fn dc_block(&mut self, x: f32) -> f32 {
+        let r = 0.995;
+        let y = x - self.dc_blocker_x1 + r * self.dc_blocker_y1;
+        self.dc_blocker_x1 = x;
+        self.dc_blocker_y1 = y;
+        y
+    }
+
+    pub fn output(&mut self, input: f32) -> f32 {
+        let shaper = self.shaper;
+        let shaped = self.oversampler.process(input, |x| shaper.waveshape(x));
+        self.dc_block(shaped) * 0.7
+    }
+
+    pub fn reset(&mut self) {
+        self.oversampler.reset();
+        self.dc_blocker_x1 = 0.0;
+        self.dc_blocker_y1 = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &Distortion) {
+        self.oversampler.copy_state_from(&other.oversampler);
+        self.dc_blocker_x1 = other.dc_blocker_x1;
+        self.dc_blocker_y1 = other.dc_blocker_y1;
+    }
+}
+
+impl Shaper {
     fn waveshape(&self, x: f32) -> f32 {
         match self.dist_type {
             DistortionType::Tube => self.tube_shape(x),
@@ -249,34 +188,6 @@ impl Distortion {
             sign * compressed.min(threshold)
         }
     }
-
-    fn dc_block(&mut self, x: f32) -> f32 {
-        let r = 0.995;
-        let y = x - self.dc_blocker_x1 + r * self.dc_blocker_y1;
-        self.dc_blocker_x1 = x;
-        self.dc_blocker_y1 = y;
-        y
-    }
-
-    pub fn output(&mut self, input: f32) -> f32 {
-        let input64 = input as f64;
-
-        let (a, b) = self.up_filter.upsample(input64);
-
-        let y0 = self.waveshape(a as f32);
-        let y1 = self.waveshape(b as f32);
-
-        let downsampled = self.down_filter.downsample(y0 as f64, y1 as f64) as f32;
-
-        self.dc_block(downsampled) * 0.7
-    }
-
-    pub fn reset(&mut self) {
-        self.up_filter.reset();
-        self.down_filter.reset();
-        self.dc_blocker_x1 = 0.0;
-        self.dc_blocker_y1 = 0.0;
-    }
 }
 
 #[cfg(test)]
@@ -299,6 +210,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_copy_state_continues_output() {
+        let mut dist = Distortion::default();
+        dist.drive(5.0).oversample(OversampleFactor::X4);
+        for i in 0..64 {
+            dist.output((i as f32 * 0.3).sin());
+        }
+        let mut copy = Distortion::default();
+        copy.drive(5.0).oversample(OversampleFactor::X4);
+        copy.copy_state_from(&dist);
+        for i in 64..128 {
+            let input = (i as f32 * 0.3).sin();
+            assert_eq!(copy.output(input), dist.output(input));
+        }
+    }
+
     #[test]
     fn test_distortion_output_bounded() {
         let mut dist = Distortion::default();
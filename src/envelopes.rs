@@ -122,6 +122,8 @@ impl Default for Envelope {
 }
 
 impl Envelope {
+    pub fn reset(&mut self) {}
+
     pub fn copy_state_from(&mut self, _other: &Envelope) {}
 }
 
@@ -1,4 +1,4 @@
-use crate::Signal;
+use crate::{Signal, utils::flush_denormal};
 
 pub struct LowpassFilter {
     b0: f32,
@@ -55,7 +55,7 @@ impl LowpassFilter {
         self.x2 = self.x1;
         self.x1 = input;
         self.y2 = self.y1;
-        self.y1 = output;
+        self.y1 = flush_denormal(output);
 
         output
     }
@@ -86,6 +86,13 @@ impl LowpassFilter {
         self.a2 = a2 / a0;
     }
 
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
     pub fn copy_state_from(&mut self, other: &LowpassFilter) {
         self.x1 = other.x1;
         self.x2 = other.x2;
@@ -149,7 +156,7 @@ impl HighpassFilter {
         self.x2 = self.x1;
         self.x1 = input;
         self.y2 = self.y1;
-        self.y1 = output;
+        self.y1 = flush_denormal(output);
 
         output
     }
@@ -180,6 +187,13 @@ impl HighpassFilter {
         self.a2 = a2 / a0;
     }
 
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
     pub fn copy_state_from(&mut self, other: &HighpassFilter) {
         self.x1 = other.x1;
         self.x2 = other.x2;
@@ -188,6 +202,102 @@ impl HighpassFilter {
     }
 }
 
+pub struct BandpassFilter {
+    b0: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+    frequency: f32,
+    q: f32,
+}
+
+impl Default for BandpassFilter {
+    fn default() -> Self {
+        let mut filter = Self {
+            b0: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+            frequency: 0.045,
+            q: 0.707,
+        };
+        filter.update_coefficients();
+        filter
+    }
+}
+
+impl BandpassFilter {
+    pub fn freq(&mut self, normalized_frequency: f32) -> &mut Self {
+        let frequency = normalized_frequency.clamp(0.001, 0.99);
+        if frequency != self.frequency {
+            self.frequency = frequency;
+            self.update_coefficients();
+        }
+        self
+    }
+
+    pub fn q(&mut self, q: f32) -> &mut Self {
+        let q = q.max(0.1);
+        if q != self.q {
+            self.q = q;
+            self.update_coefficients();
+        }
+        self
+    }
+
+    pub(crate) fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.b2 * self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
+
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = output;
+
+        output
+    }
+
+    pub fn output(&mut self, input: f32, freq_mod: f32, _signal: &mut Signal) -> f32 {
+        self.freq(freq_mod);
+        self.process(input)
+    }
+
+    fn update_coefficients(&mut self) {
+        let omega = std::f32::consts::PI * self.frequency;
+        let sin_omega = omega.sin();
+        let cos_omega = omega.cos();
+        let alpha = sin_omega / (2.0 * self.q);
+
+        let a0 = 1.0 + alpha;
+
+        self.b0 = alpha / a0;
+        self.b2 = -alpha / a0;
+        self.a1 = -2.0 * cos_omega / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &BandpassFilter) {
+        self.x1 = other.x1;
+        self.x2 = other.x2;
+        self.y1 = other.y1;
+        self.y2 = other.y2;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -318,4 +428,24 @@ mod tests {
             assert!(hp_out.is_finite());
         }
     }
+
+    #[test]
+    fn test_bandpass_filter_passes_center_only() {
+        let sample_rate = 44100.0;
+        let duration_samples = 4410;
+        let center_normalized = 1000.0 / (sample_rate / 2.0);
+
+        let mut gains = Vec::new();
+        for freq in [100.0, 1000.0, 10000.0] {
+            let mut filter = BandpassFilter::default();
+            filter.freq(center_normalized).q(2.0);
+            let input = generate_sine_wave(freq, sample_rate, duration_samples);
+            let output: Vec<f32> = input.iter().map(|&x| filter.process(x)).collect();
+            gains.push(calculate_rms(&output[1000..]) / calculate_rms(&input[1000..]));
+        }
+
+        assert!(gains[1] > 0.9, "Center frequency should pass at unity gain");
+        assert!(gains[0] < 0.2, "Low frequency should be attenuated");
+        assert!(gains[2] < 0.2, "High frequency should be attenuated");
+    }
 }
@@ -82,6 +82,10 @@ impl Flanger {
             MIN_DELAY_SAMPLES as f32 + (MAX_DELAY_SAMPLES - MIN_DELAY_SAMPLES) as f32 * self.depth;
     }
 
+    pub fn reset(&mut self) {
+        self.delay.reset();
+    }
+
     pub fn copy_state_from(&mut self, other: &Flanger) {
         self.delay.copy_state_from(&other.delay);
         self.lfo.copy_phase_from(&other.lfo);
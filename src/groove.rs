@@ -0,0 +1,213 @@
+use std::fs;
+use std::path::Path;
+
+/// Groove steps in a bar, one per 16th note.
+pub const GROOVE_STEPS: usize = 16;
+
+/// Per-16th timing and velocity offsets applied to a track. Timing offsets
+/// are fractions of a 16th between -0.5 and 0.5; positions between two
+/// steps move proportionally, so notes keep their order and lengths follow
+/// the grid they were written on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Groove {
+    name: String,
+    timing: [f32; GROOVE_STEPS],
+    velocity: [i32; GROOVE_STEPS],
+}
+
+impl Default for Groove {
+    fn default() -> Self {
+        Self::straight()
+    }
+}
+
+impl Groove {
+    pub fn straight() -> Self {
+        Self {
+            name: "Straight".to_string(),
+            timing: [0.0; GROOVE_STEPS],
+            velocity: [0; GROOVE_STEPS],
+        }
+    }
+
+    /// The built-in templates, starting with `straight`.
+    pub fn presets() -> Vec<Self> {
+        let preset = |name: &str, steps: &[(f32, i32)]| {
+            let mut groove = Self::straight();
+            groove.name = name.to_string();
+            groove.fill(steps);
+            groove
+        };
+        vec![
+            Self::straight(),
+            preset("Push", &[(0.0, 0), (0.0, 0), (-0.15, 8), (0.0, 0)]),
+            preset(
+                "Laid Back",
+                &[
+                    (0.0, 0),
+                    (0.05, -10),
+                    (0.1, 0),
+                    (0.05, -10),
+                    (0.2, 10),
+                    (0.05, -10),
+                    (0.1, 0),
+                    (0.05, -10),
+                ],
+            ),
+            preset("Accent", &[(0.0, 20), (0.0, -20), (0.0, 0), (0.0, -20)]),
+        ]
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        Self::presets().into_iter().find(|g| g.name == name)
+    }
+
+    /// Parses a groove template: one `timing [velocity]` line per 16th, with
+    /// `!` or `#` starting a comment line. Fewer than 16 lines repeat across
+    /// the bar.
+    pub fn from_text(name: &str, text: &str) -> Result<Self, String> {
+        let steps = text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('!') && !l.starts_with('#'))
+            .map(parse_step)
+            .collect::<Result<Vec<_>, _>>()?;
+        if steps.is_empty() {
+            return Err("groove has no steps".into());
+        }
+        if steps.len() > GROOVE_STEPS {
+            return Err(format!(
+                "expected at most {} steps, found {}",
+                GROOVE_STEPS,
+                steps.len()
+            ));
+        }
+        let mut groove = Self::straight();
+        groove.name = name.to_string();
+        groove.fill(&steps);
+        Ok(groove)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        Self::from_text(name, &text)
+    }
+
+    fn fill(&mut self, steps: &[(f32, i32)]) {
+        for i in 0..GROOVE_STEPS {
+            (self.timing[i], self.velocity[i]) = steps[i % steps.len()];
+        }
+    }
+
+    /// Adds swing on top of the template. `percent` is the share of each
+    /// pair of 16ths given to the first one: 50 is straight, 66 a triplet
+    /// shuffle and 75 (the maximum) a dotted feel.
+    pub fn with_swing(mut self, percent: f32) -> Self {
+        let delay = (percent.clamp(50.0, 75.0) - 50.0) / 50.0;
+        for timing in self.timing.iter_mut().skip(1).step_by(2) {
+            *timing = (*timing + delay).clamp(-0.5, 0.5);
+        }
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_straight(&self) -> bool {
+        self.timing.iter().all(|&t| t == 0.0) && self.velocity.iter().all(|&v| v == 0)
+    }
+
+    /// Moves a position measured in 16ths from the start of a bar
+    /// `bar_steps` 16ths long. The template restarts at every bar line, so
+    /// bars shorter than 16 steps use only its start.
+    pub fn shift(&self, position: f32, bar_steps: usize) -> f32 {
+        let step = position.floor();
+        let frac = position - step;
+        let here = self.timing[step as usize % bar_steps % GROOVE_STEPS];
+        let next = self.timing[(step as usize + 1) % bar_steps % GROOVE_STEPS];
+        step + here + frac * (1.0 + next - here)
+    }
+
+    /// Offsets the velocity of a note starting at `position` (in 16ths from
+    /// the start of a bar `bar_steps` long) by that of the nearest step.
+    pub fn accent(&self, position: f32, bar_steps: usize, velocity: u8) -> u8 {
+        let step = position.round() as usize % bar_steps % GROOVE_STEPS;
+        (velocity as i32 + self.velocity[step]).clamp(1, 127) as u8
+    }
+}
+
+fn parse_step(line: &str) -> Result<(f32, i32), String> {
+    let mut fields = line.split_whitespace();
+    let token = fields.next().unwrap_or("");
+    let timing: f32 = token
+        .parse()
+        .map_err(|_| format!("invalid timing '{}'", token))?;
+    if !(-0.5..=0.5).contains(&timing) {
+        return Err(format!("timing '{}' is outside -0.5..0.5", token));
+    }
+    let velocity = match fields.next() {
+        Some(token) => token
+            .parse()
+            .map_err(|_| format!("invalid velocity '{}'", token))?,
+        None => 0,
+    };
+    Ok((timing, velocity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swing_delays_off_sixteenths() {
+        let groove = Groove::straight().with_swing(75.0);
+        assert_eq!(groove.shift(0.0, 16), 0.0);
+        assert_eq!(groove.shift(1.0, 16), 1.5);
+        assert_eq!(groove.shift(2.0, 16), 2.0);
+        // Halfway through the first 16th lands halfway to the delayed second.
+        assert_eq!(groove.shift(0.5, 16), 0.75);
+        assert_eq!(groove.shift(16.0 + 3.0, 16), 19.5);
+        // A 7/16 bar starts the template again on its eighth step.
+        assert_eq!(groove.shift(7.0, 16), 7.5);
+        assert_eq!(groove.shift(7.0, 7), 7.0);
+        assert_eq!(groove.shift(6.5, 7), 6.5);
+
+        let triplet = Groove::straight().with_swing(66.0);
+        assert!((triplet.shift(1.0, 16) - 1.32).abs() < 1e-5);
+        assert_eq!(Groove::straight().with_swing(50.0), Groove::straight());
+    }
+
+    #[test]
+    fn test_parse_groove() {
+        let text = "! two-step groove\n0.0 10\n\n-0.25\n";
+        let groove = Groove::from_text("two.groove", text).unwrap();
+        assert_eq!(groove.name(), "two.groove");
+        assert_eq!(groove.shift(1.0, 16), 0.75);
+        assert_eq!(groove.shift(5.0, 16), 4.75);
+        assert_eq!(groove.accent(4.0, 16, 100), 110);
+        assert_eq!(groove.accent(3.0, 16, 100), 100);
+        assert_eq!(groove.accent(2.0, 16, 120), 127);
+        assert_eq!(groove.accent(5.0, 5, 100), 110);
+
+        assert!(Groove::from_text("empty", "! nothing\n").is_err());
+        assert!(Groove::from_text("late", "0.75\n").is_err());
+        assert!(Groove::from_text("bad", "0.1 loud\n").is_err());
+        assert!(Groove::from_text("long", &"0\n".repeat(17)).is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        let presets = Groove::presets();
+        assert!(presets[0].is_straight());
+        for groove in &presets[1..] {
+            assert!(!groove.is_straight());
+            assert_eq!(Groove::preset(groove.name()).as_ref(), Some(groove));
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{oscillators::Osc, signal::Signal};
+
+const REAL_COEFFS: [f32; 4] = [0.692_387_8, 0.936_065_4, 0.988_229_5, 0.998_748_85];
+const IMAG_COEFFS: [f32; 4] = [0.402_192_12, 0.856_171_1, 0.972_290_95, 0.995_288_5];
+
+struct HilbertSection {
+    a: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl HilbertSection {
+    fn new(coefficient: f32) -> Self {
+        Self {
+            a: coefficient * coefficient,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let y = self.a * (input + self.y2) - self.x2;
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
+    fn copy_state_from(&mut self, other: &HilbertSection) {
+        self.x1 = other.x1;
+        self.x2 = other.x2;
+        self.y1 = other.y1;
+        self.y2 = other.y2;
+    }
+}
+
+pub struct Hilbert {
+    real: [HilbertSection; 4],
+    imag: [HilbertSection; 4],
+    real_delay: f32,
+}
+
+impl Default for Hilbert {
+    fn default() -> Self {
+        Self {
+            real: REAL_COEFFS.map(HilbertSection::new),
+            imag: IMAG_COEFFS.map(HilbertSection::new),
+            real_delay: 0.0,
+        }
+    }
+}
+
+impl Hilbert {
+    pub fn output(&mut self, input: f32) -> (f32, f32) {
+        let mut real = input;
+        for section in &mut self.real {
+            real = section.process(real);
+        }
+        let mut imag = input;
+        for section in &mut self.imag {
+            imag = section.process(imag);
+        }
+
+        let delayed = self.real_delay;
+        self.real_delay = real;
+        (delayed, imag)
+    }
+
+    pub fn reset(&mut self) {
+        for section in self.real.iter_mut().chain(self.imag.iter_mut()) {
+            section.reset();
+        }
+        self.real_delay = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &Hilbert) {
+        for (s, o) in self.real.iter_mut().zip(&other.real) {
+            s.copy_state_from(o);
+        }
+        for (s, o) in self.imag.iter_mut().zip(&other.imag) {
+            s.copy_state_from(o);
+        }
+        self.real_delay = other.real_delay;
+    }
+}
+
+pub struct FrequencyShifter {
+    hilbert: Hilbert,
+    shift: f32,
+    phase: f32,
+}
+
+impl Default for FrequencyShifter {
+    fn default() -> Self {
+        Self {
+            hilbert: Hilbert::default(),
+            shift: 0.0,
+            phase: 0.0,
+        }
+    }
+}
+
+impl FrequencyShifter {
+    pub fn shift(&mut self, hz: f32) -> &mut Self {
+        self.shift = hz;
+        self
+    }
+
+    pub fn output(&mut self, input: f32, signal: &mut Signal) -> (f32, f32) {
+        let (real, imag) = self.hilbert.output(input);
+
+        let angle = self.phase * std::f32::consts::TAU;
+        let (sin, cos) = angle.sin_cos();
+        self.phase = (self.phase + self.shift / signal.sample_rate as f32).rem_euclid(1.0);
+
+        let up = real * cos + imag * sin;
+        let down = real * cos - imag * sin;
+        (up, down)
+    }
+
+    pub fn reset(&mut self) {
+        self.hilbert.reset();
+        self.phase = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &FrequencyShifter) {
+        self.hilbert.copy_state_from(&other.hilbert);
+        self.phase = other.phase;
+    }
+}
+
+pub struct RingModulator {
+    carrier: Osc,
+    mix: f32,
+}
+
+impl Default for RingModulator {
+    fn default() -> Self {
+        let mut carrier = Osc::default();
+        carrier.sin().freq(440.0);
+        Self { carrier, mix: 1.0 }
+    }
+}
+
+impl RingModulator {
+    pub fn freq(&mut self, hz: f32) -> &mut Self {
+        self.carrier.freq(hz);
+        self
+    }
+
+    pub fn mix(&mut self, amount: f32) -> &mut Self {
+        self.mix = amount.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn output(&mut self, input: f32, signal: &mut Signal) -> f32 {
+        let carrier = self.carrier.output(signal);
+        input * (1.0 - self.mix) + input * carrier * self.mix
+    }
+
+    pub fn reset(&mut self) {
+        self.carrier.reset();
+    }
+
+    pub fn copy_state_from(&mut self, other: &RingModulator) {
+        self.carrier.copy_phase_from(&other.carrier);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(i: usize, freq: f32) -> f32 {
+        (i as f32 * freq * std::f32::consts::TAU / 44100.0).sin()
+    }
+
+    fn zero_crossings(samples: &[f32]) -> usize {
+        samples
+            .windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count()
+    }
+
+    #[test]
+    fn test_hilbert_outputs_are_in_quadrature() {
+        for freq in [100.0, 1000.0, 8000.0] {
+            let mut hilbert = Hilbert::default();
+            for i in 0..44100 {
+                let (real, imag) = hilbert.output(sine(i, freq));
+                if i > 4410 {
+                    let magnitude = (real * real + imag * imag).sqrt();
+                    assert!(
+                        (magnitude - 1.0).abs() < 0.05,
+                        "magnitude {} at {}Hz",
+                        magnitude,
+                        freq
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_frequency_shifter_moves_up_and_down() {
+        let mut shifter = FrequencyShifter::default();
+        shifter.shift(200.0);
+        let mut signal = Signal::new(44100);
+
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        for i in 0..44100 {
+            let (u, d) = shifter.output(sine(i, 1000.0), &mut signal);
+            up.push(u);
+            down.push(d);
+        }
+
+        let up_hz = zero_crossings(&up[4410..]) as f32 / 2.0 / 0.9;
+        let down_hz = zero_crossings(&down[4410..]) as f32 / 2.0 / 0.9;
+        assert!((up_hz - 1200.0).abs() < 10.0, "up at {}Hz", up_hz);
+        assert!((down_hz - 800.0).abs() < 10.0, "down at {}Hz", down_hz);
+    }
+
+    #[test]
+    fn test_ring_modulator_dry_mix() {
+        let mut ring = RingModulator::default();
+        ring.freq(300.0).mix(0.0);
+        let mut signal = Signal::new(44100);
+
+        for i in 0..100 {
+            let input = sine(i, 440.0);
+            assert_eq!(ring.output(input, &mut signal), input);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Signal;
+use crate::{Signal, Tuning};
 use std::array;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -16,6 +16,7 @@ pub enum KeyState {
 pub struct Key {
     pub pitch: u8,
     pub freq: f32,
+    pub velocity: f32,
     pub state: KeyState,
 }
 
@@ -28,6 +29,8 @@ impl Key {
 pub struct Keyboard<V> {
     voices: [V; 128],
     states: [KeyState; 128],
+    velocities: [u8; 128],
+    frequencies: [f32; 128],
 }
 
 impl<V: Default> Keyboard<V> {
@@ -35,6 +38,8 @@ impl<V: Default> Keyboard<V> {
         Keyboard {
             voices: array::from_fn(|_| V::default()),
             states: array::from_fn(|_| KeyState::Idle),
+            velocities: [crate::DEFAULT_VELOCITY; 128],
+            frequencies: frequency_table(&Tuning::default()),
         }
     }
 }
@@ -44,16 +49,26 @@ impl<V> Keyboard<V> {
         Keyboard {
             voices: array::from_fn(|_| builder()),
             states: array::from_fn(|_| KeyState::Idle),
+            velocities: [crate::DEFAULT_VELOCITY; 128],
+            frequencies: frequency_table(&Tuning::default()),
         }
     }
 
-    pub fn update(&mut self, events: Vec<crate::NoteEvent>, signal: &Signal) {
-        for event in events {
+    pub fn tuning(&mut self, tuning: &Tuning) -> &mut Self {
+        self.frequencies = frequency_table(tuning);
+        self
+    }
+
+    pub fn update(&mut self, events: &[crate::NoteEvent], signal: &Signal) {
+        for &event in events {
             match event {
-                crate::NoteEvent::Press { pitch, .. } => {
+                crate::NoteEvent::Press {
+                    pitch, velocity, ..
+                } => {
                     self.states[pitch as usize] = KeyState::Pressed {
                         pressed_at: signal.position,
                     };
+                    self.velocities[pitch as usize] = velocity;
                 }
                 crate::NoteEvent::Release { pitch } => {
                     if let KeyState::Pressed { pressed_at } = self.states[pitch as usize] {
@@ -69,10 +84,10 @@ impl<V> Keyboard<V> {
 
     pub fn per_key(&mut self, mut f: impl FnMut(&mut V, Key)) {
         for pitch in 0..128u8 {
-            let frequency = 440.0 * 2.0_f32.powf((pitch as f32 - 69.0) / 12.0);
             let key = Key {
                 pitch,
-                freq: frequency,
+                freq: self.frequencies[pitch as usize],
+                velocity: self.velocities[pitch as usize] as f32 / 127.0,
                 state: self.states[pitch as usize],
             };
             f(&mut self.voices[pitch as usize], key);
@@ -80,6 +95,10 @@ impl<V> Keyboard<V> {
     }
 }
 
+fn frequency_table(tuning: &Tuning) -> [f32; 128] {
+    array::from_fn(|pitch| tuning.frequency(pitch as i32))
+}
+
 impl<V: Default> Default for Keyboard<V> {
     fn default() -> Self {
         Self::new()
@@ -97,9 +116,10 @@ mod tests {
         let events = vec![crate::NoteEvent::Press {
             pitch: 60,
             degree: 0,
+            velocity: crate::DEFAULT_VELOCITY,
         }];
 
-        keyboard.update(events, &signal);
+        keyboard.update(&events, &signal);
 
         assert!(matches!(
             keyboard.states[60],
@@ -115,12 +135,13 @@ mod tests {
         let press_events = vec![crate::NoteEvent::Press {
             pitch: 60,
             degree: 0,
+            velocity: crate::DEFAULT_VELOCITY,
         }];
-        keyboard.update(press_events, &signal);
+        keyboard.update(&press_events, &signal);
 
         signal.position = 1000;
         let release_events = vec![crate::NoteEvent::Release { pitch: 60 }];
-        keyboard.update(release_events, &signal);
+        keyboard.update(&release_events, &signal);
 
         assert!(matches!(
             keyboard.states[60],
@@ -138,8 +159,9 @@ mod tests {
         let events = vec![crate::NoteEvent::Press {
             pitch: 60,
             degree: 0,
+            velocity: crate::DEFAULT_VELOCITY,
         }];
-        keyboard.update(events, &signal);
+        keyboard.update(&events, &signal);
 
         let mut visited_pitches = Vec::new();
         keyboard.per_key(|_voice, key| {
@@ -150,6 +172,39 @@ mod tests {
         assert!(visited_pitches.contains(&60));
     }
 
+    #[test]
+    fn test_keyboard_key_velocity() {
+        let mut keyboard: Keyboard<()> = Keyboard::new();
+        let signal = Signal::new(44100);
+        let events = vec![crate::NoteEvent::Press {
+            pitch: 60,
+            degree: 0,
+            velocity: 127,
+        }];
+        keyboard.update(&events, &signal);
+
+        keyboard.per_key(|_voice, key| {
+            if key.pitch == 60 {
+                assert_eq!(key.velocity, 1.0);
+            }
+        });
+    }
+
+    #[test]
+    fn test_keyboard_uses_tuning() {
+        let mut keyboard: Keyboard<()> = Keyboard::new();
+        keyboard.tuning(&Tuning::edo(24));
+
+        keyboard.per_key(|_voice, key| {
+            if key.pitch == 69 {
+                assert!((key.freq - 440.0).abs() < 1e-3);
+            }
+            if key.pitch == 93 {
+                assert!((key.freq - 880.0).abs() < 1e-2);
+            }
+        });
+    }
+
     #[test]
     fn test_keyboard_per_key_with_state() {
         let mut keyboard: Keyboard<String> = Keyboard::with_builder(|| "voice".to_string());
@@ -157,8 +212,9 @@ mod tests {
         let events = vec![crate::NoteEvent::Press {
             pitch: 60,
             degree: 0,
+            velocity: crate::DEFAULT_VELOCITY,
         }];
-        keyboard.update(events, &signal);
+        keyboard.update(&events, &signal);
 
         keyboard.per_key(|voice, key| {
             if key.pitch == 60 {
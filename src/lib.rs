@@ -7,6 +7,7 @@
 static A: rlsf::GlobalTlsf = rlsf::GlobalTlsf::new();
 
 mod allpass;
+mod bitcrusher;
 mod clock;
 mod comb;
 mod delay;
@@ -15,20 +16,30 @@ mod envelopes;
 mod filters;
 mod flanger;
 mod gate_ramp;
+mod groove;
+mod hilbert;
 mod keyboard;
 #[cfg(feature = "live")]
 mod live;
+mod mix_bus;
+mod notation;
 mod oscillators;
+mod oversampler;
+mod pitch_shift;
 mod ramp;
 mod reverb;
+mod sample;
 mod scale;
 mod signal;
 mod track;
+mod tuning;
 
 mod utils;
+mod vocoder;
 #[cfg(feature = "wav")]
 mod wav;
 
+pub use bitcrusher::*;
 pub use clock::*;
 pub use delay::*;
 pub use distortion::*;
@@ -36,17 +47,25 @@ pub use envelopes::*;
 pub use filters::*;
 pub use flanger::*;
 pub use gate_ramp::*;
+pub use groove::*;
+pub use hilbert::*;
 pub use keyboard::*;
 #[cfg(feature = "live")]
 pub use live::*;
+pub use mix_bus::*;
 pub use oscillators::*;
+pub use oversampler::*;
+pub use pitch_shift::*;
 pub use ramp::*;
 pub use reverb::*;
+pub use sample::*;
 pub use scale::*;
 pub use signal::*;
 pub use track::*;
+pub use tuning::*;
 #[cfg(feature = "tui")]
 pub mod tui;
 pub use utils::*;
+pub use vocoder::*;
 #[cfg(feature = "wav")]
 pub use wav::*;
@@ -38,7 +38,11 @@ impl AudioPlayer {
                 let mut synth = synth.lock().unwrap();
 
                 for frame in data.chunks_mut(channels) {
-                    let sample = assert_no_alloc(|| synth(&mut signal_lock).clamp(-1., 1.));
+                    let sample = assert_no_alloc(|| {
+                        let sample = synth(&mut signal_lock);
+                        signal_lock.add_sample(sample);
+                        signal_lock.mix().clamp(-1., 1.)
+                    });
 
                     for channel_sample in frame.iter_mut() {
                         *channel_sample = sample;
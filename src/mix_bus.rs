@@ -0,0 +1,126 @@
+use crate::utils::db_to_gain;
+
+const VOLUME_SMOOTHING_SECONDS: f32 = 0.01;
+const LIMITER_RELEASE_SECONDS: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BusStage {
+    #[default]
+    Clean,
+    SoftClip,
+    Limiter,
+}
+
+pub struct MixBus {
+    stage: BusStage,
+    ceiling: f32,
+    volume: f32,
+    envelope: f32,
+}
+
+impl Default for MixBus {
+    fn default() -> Self {
+        Self {
+            stage: BusStage::Clean,
+            ceiling: 1.0,
+            volume: 1.0,
+            envelope: 0.0,
+        }
+    }
+}
+
+impl MixBus {
+    pub fn stage(&mut self, stage: BusStage) -> &mut Self {
+        self.stage = stage;
+        self
+    }
+
+    pub fn ceiling_db(&mut self, db: f32) -> &mut Self {
+        self.ceiling = db_to_gain(db.min(0.0));
+        self
+    }
+
+    /// Runs the summed bus through the output stage, then applies `volume`
+    /// through a short one-pole smoother so level changes don't click.
+    pub fn process(&mut self, sum: f32, volume: f32, sample_rate: usize) -> f32 {
+        let staged = match self.stage {
+            BusStage::Clean => sum,
+            BusStage::SoftClip => self.ceiling * (sum / self.ceiling).tanh(),
+            BusStage::Limiter => {
+                let level = sum.abs();
+                if level > self.envelope {
+                    self.envelope = level;
+                } else {
+                    let release = (-1.0 / (LIMITER_RELEASE_SECONDS * sample_rate as f32)).exp();
+                    self.envelope = level + release * (self.envelope - level);
+                }
+                if self.envelope > self.ceiling {
+                    sum * self.ceiling / self.envelope
+                } else {
+                    sum
+                }
+            }
+        };
+
+        let smoothing = 1.0 - (-1.0 / (VOLUME_SMOOTHING_SECONDS * sample_rate as f32)).exp();
+        self.volume += (volume - self.volume) * smoothing;
+        staged * self.volume
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soft_clip_and_limiter_stay_under_ceiling() {
+        for stage in [BusStage::SoftClip, BusStage::Limiter] {
+            let mut bus = MixBus::default();
+            bus.stage(stage).ceiling_db(-6.0);
+            for i in 0..4410 {
+                let sum = (i as f32 * 0.05).sin() * 4.0;
+                let out = bus.process(sum, 1.0, 44100);
+                assert!(out.abs() <= 0.502, "{:?} let {} through", stage, out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clean_stage_passes_the_sum() {
+        let mut bus = MixBus::default();
+        assert_eq!(bus.process(-1.4, 1.0, 44100), -1.4);
+    }
+
+    #[test]
+    fn test_limiter_releases_after_a_peak() {
+        let mut bus = MixBus::default();
+        bus.stage(BusStage::Limiter);
+        assert!((bus.process(4.0, 1.0, 44100) - 1.0).abs() < 1e-6);
+
+        let held = bus.process(0.5, 1.0, 44100);
+        assert!(held < 0.2, "gain recovered at once: {}", held);
+        let mut out = held;
+        for _ in 0..44100 {
+            out = bus.process(0.5, 1.0, 44100);
+        }
+        assert!((out - 0.5).abs() < 1e-3, "gain still reduced: {}", out);
+    }
+
+    #[test]
+    fn test_volume_is_smoothed() {
+        let mut bus = MixBus::default();
+        let first = bus.process(1.0, 0.0, 44100);
+        assert!(first > 0.9, "volume jumped to {}", first);
+
+        // A 10 ms smoother settles well within 100 ms.
+        let mut last = first;
+        for _ in 0..4410 {
+            last = bus.process(1.0, 0.0, 44100);
+        }
+        assert!(last < 1e-3, "volume still at {}", last);
+    }
+}
@@ -0,0 +1,908 @@
+use crate::track::{ACCENT_VELOCITY, GHOST_VELOCITY, MAX_POLYMETER_BARS};
+use crate::utils::gcd;
+use crate::{
+    Bar, Chord, ChordRoot, Division, Interpolation, Item, LaneDivision, LaneItem, LayerMode,
+    ParsedLane, ParsedNote, ParsedTrackAST, Shift, TimeSignature, Voicing,
+};
+use std::fmt::{self, Write};
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// Prints canonical notation: repeats and sections are written out, apart
+/// from runs of identical bars which use `x<n>`, and every layer and lane
+/// goes on a line of its own.
+impl fmt::Display for ParsedTrackAST {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.layer_mode {
+            LayerMode::Stretch => {}
+            LayerMode::Polymeter { length: None } => writeln!(f, "polymeter")?,
+            LayerMode::Polymeter {
+                length: Some(length),
+            } => writeln!(f, "polymeter {}", length)?,
+        }
+        if self.meter != TimeSignature::default() {
+            writeln!(f, "meter {}", self.meter)?;
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n& ")?;
+            }
+            let mut first = true;
+            write_repeated(f, &layer.bars, |f, bar| {
+                if let Some(meter) = bar.meter {
+                    // A space keeps it apart from a repeat count before it.
+                    if !first {
+                        f.write_char(' ')?;
+                    }
+                    write!(f, "{}", meter)?;
+                }
+                first = false;
+                f.write_char('(')?;
+                write_divisions(f, &bar.divisions)?;
+                f.write_char(')')
+            })?;
+        }
+        for lane in &self.lanes {
+            writeln!(f)?;
+            write!(f, "{}", lane)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParsedLane {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        if self.interpolation == Interpolation::Ramp {
+            f.write_str("ramp ")?;
+        }
+        write_repeated(f, &self.bars, |f, bar| {
+            f.write_char('(')?;
+            write_lane_divisions(f, bar)?;
+            f.write_char(')')
+        })
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_item(f, self, false)
+    }
+}
+
+fn write_repeated<T: PartialEq>(
+    f: &mut fmt::Formatter,
+    bars: &[T],
+    mut write_bar: impl FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+) -> fmt::Result {
+    let mut i = 0;
+    while i < bars.len() {
+        let count = bars[i..].iter().take_while(|&b| *b == bars[i]).count();
+        write_bar(f, &bars[i])?;
+        if count > 1 {
+            write!(f, "x{}", count)?;
+        }
+        i += count;
+    }
+    Ok(())
+}
+
+fn write_divisions(f: &mut fmt::Formatter, divisions: &[Division]) -> fmt::Result {
+    for (i, div) in divisions.iter().enumerate() {
+        if i > 0 {
+            f.write_char('/')?;
+        }
+        // `C/G` reads as a slash chord, so a plain triad after a bare letter
+        // chord spells out its quality.
+        let after_chord = i > 0 && ends_with_letter_chord(&divisions[i - 1]);
+        write_item(f, &div.item, after_chord)?;
+        for _ in 1..div.weight {
+            f.write_char('*')?;
+        }
+    }
+    Ok(())
+}
+
+fn ends_with_letter_chord(division: &Division) -> bool {
+    let mut item = &division.item;
+    while let Item::Shift(inner, shift) = item
+        && shift.degrees == 0
+    {
+        item = inner;
+    }
+    matches!(
+        item,
+        Item::Chord(Chord {
+            root: ChordRoot::Letter(_),
+            bass: None,
+            velocity: None,
+            tie: false,
+            slur: false,
+            ..
+        })
+    ) && division.weight == 1
+}
+
+fn write_item(f: &mut fmt::Formatter, item: &Item, after_chord: bool) -> fmt::Result {
+    match item {
+        Item::Note(note) => write_note(f, note),
+        Item::Chord(chord) => write_chord(f, chord, after_chord),
+        Item::Rest => f.write_char('_'),
+        Item::Sequence(divisions) => {
+            f.write_char('(')?;
+            write_divisions(f, divisions)?;
+            f.write_char(')')
+        }
+        Item::Polyphony(layers) => write_options(f, '{', '&', '}', layers),
+        Item::Choice(options) => write_options(f, '[', '|', ']', options),
+        Item::Shift(inner, shift) => write_shift(f, inner, *shift),
+        Item::Chance(inner, percent) => {
+            write_item(f, inner, after_chord)?;
+            write!(f, "?{}", percent)
+        }
+    }
+}
+
+fn write_options(
+    f: &mut fmt::Formatter,
+    open: char,
+    separator: char,
+    close: char,
+    options: &[Vec<Division>],
+) -> fmt::Result {
+    f.write_char(open)?;
+    for (i, divisions) in options.iter().enumerate() {
+        if i > 0 {
+            f.write_char(separator)?;
+        }
+        write_divisions(f, divisions)?;
+    }
+    f.write_char(close)
+}
+
+fn write_shift(f: &mut fmt::Formatter, inner: &Item, shift: Shift) -> fmt::Result {
+    let mark = if shift.octaves > 0 { '\'' } else { ',' };
+    for _ in 0..shift.octaves.abs() {
+        f.write_char(mark)?;
+    }
+    if shift.degrees == 0 {
+        return write_item(f, inner, false);
+    }
+    // Only groups take a transposition.
+    match inner {
+        Item::Sequence(_) | Item::Polyphony(_) => write_item(f, inner, false)?,
+        _ => {
+            f.write_char('(')?;
+            write_item(f, inner, false)?;
+            f.write_char(')')?;
+        }
+    }
+    write!(f, "{:+}", shift.degrees)
+}
+
+fn write_note(f: &mut fmt::Formatter, note: &ParsedNote) -> fmt::Result {
+    write!(f, "{}", note.degree)?;
+    let accidental = if note.chromatic_shift > 0 { '+' } else { '-' };
+    for _ in 0..note.chromatic_shift.abs() {
+        f.write_char(accidental)?;
+    }
+    write_modifiers(f, note.velocity, note.tie, note.slur)
+}
+
+fn write_chord(f: &mut fmt::Formatter, chord: &Chord, after_chord: bool) -> fmt::Result {
+    match chord.root {
+        ChordRoot::Letter(root) => f.write_str(NOTE_NAMES[root.rem_euclid(12) as usize])?,
+        ChordRoot::Roman {
+            degree,
+            flat,
+            minor,
+        } => {
+            let octaves = degree.div_euclid(7);
+            let mark = if octaves > 0 { '\'' } else { ',' };
+            for _ in 0..octaves.abs() {
+                f.write_char(mark)?;
+            }
+            if flat {
+                f.write_char('b')?;
+            }
+            let numeral = NUMERALS[degree.rem_euclid(7) as usize];
+            if minor {
+                f.write_str(&numeral.to_lowercase())?;
+            } else {
+                f.write_str(numeral)?;
+            }
+        }
+    }
+    let letter = matches!(chord.root, ChordRoot::Letter(_));
+    if letter && after_chord && chord.quality.is_empty() {
+        f.write_str("maj")?;
+    } else {
+        f.write_str(&chord.quality)?;
+    }
+    for voicing in &chord.voicing {
+        match voicing {
+            Voicing::Inversion(n) => write!(f, "@{}", n)?,
+            Voicing::Spread => f.write_char('%')?,
+        }
+    }
+    if let Some(bass) = chord.bass {
+        write!(f, "/{}", NOTE_NAMES[bass.rem_euclid(12) as usize])?;
+    }
+    write_modifiers(f, chord.velocity, chord.tie, chord.slur)
+}
+
+fn write_modifiers(
+    f: &mut fmt::Formatter,
+    velocity: Option<u8>,
+    tie: bool,
+    slur: bool,
+) -> fmt::Result {
+    match velocity {
+        Some(ACCENT_VELOCITY) => f.write_char('!')?,
+        Some(GHOST_VELOCITY) => f.write_char('.')?,
+        Some(velocity) => write!(f, "^{}", velocity)?,
+        None => {}
+    }
+    if tie {
+        f.write_char('~')?;
+    }
+    if slur {
+        f.write_char('=')?;
+    }
+    Ok(())
+}
+
+fn write_lane_divisions(f: &mut fmt::Formatter, divisions: &[LaneDivision]) -> fmt::Result {
+    for (i, div) in divisions.iter().enumerate() {
+        if i > 0 {
+            f.write_char('/')?;
+        }
+        match &div.item {
+            LaneItem::Value(value) => write!(f, "{}", value)?,
+            LaneItem::Hold => f.write_char('_')?,
+            LaneItem::Sequence(inner) => {
+                f.write_char('(')?;
+                write_lane_divisions(f, inner)?;
+                f.write_char(')')?;
+            }
+        }
+        for _ in 1..div.weight {
+            f.write_char('*')?;
+        }
+    }
+    Ok(())
+}
+
+/// Transformations for generating variations of a track. Pitch transforms
+/// leave lanes alone; `augment` and `diminish` stretch lanes along with the
+/// bars so they stay in step.
+impl ParsedTrackAST {
+    /// Moves every note by `degrees` scale degrees.
+    pub fn transpose(&mut self, degrees: i32) -> &mut Self {
+        self.for_each_division(&mut |div| transpose_item(&mut div.item, degrees));
+        self
+    }
+
+    /// Mirrors every note around `axis`, so a step up becomes a step down.
+    /// Chords keep their quality and letter chords their root.
+    pub fn invert(&mut self, axis: i32) -> &mut Self {
+        self.for_each_division(&mut |div| invert_item(&mut div.item, axis));
+        self
+    }
+
+    /// Plays the bars, and everything in them, backwards. Ties and slurs
+    /// stay on the notes they were written on.
+    pub fn reverse(&mut self) -> &mut Self {
+        for layer in &mut self.layers {
+            layer.bars.reverse();
+            for bar in &mut layer.bars {
+                reverse_divisions(&mut bar.divisions);
+            }
+        }
+        self
+    }
+
+    /// Rotates the divisions of every bar left by `steps`, or right when
+    /// negative.
+    pub fn rotate(&mut self, steps: i32) -> &mut Self {
+        for bar in self.layers.iter_mut().flat_map(|l| &mut l.bars) {
+            if !bar.divisions.is_empty() {
+                let len = bar.divisions.len() as i32;
+                bar.divisions.rotate_left(steps.rem_euclid(len) as usize);
+            }
+        }
+        self
+    }
+
+    /// Stretches every bar over `factor` bars. Notes crossing the new bar
+    /// lines are tied across them; a chance or choice crossing one is rolled
+    /// separately on each side. Like the parser, polymeter lengths stop at
+    /// `MAX_POLYMETER_BARS`, and so does `factor`.
+    pub fn augment(&mut self, factor: usize) -> &mut Self {
+        let factor = factor.clamp(1, MAX_POLYMETER_BARS);
+        for layer in &mut self.layers {
+            layer.bars = layer
+                .bars
+                .iter()
+                .flat_map(|bar| {
+                    split_parts(&bar.divisions, factor)
+                        .into_iter()
+                        .map(|divisions| Bar {
+                            divisions,
+                            meter: bar.meter,
+                        })
+                })
+                .collect();
+        }
+        for lane in &mut self.lanes {
+            lane.bars = lane
+                .bars
+                .iter()
+                .flat_map(|bar| split_lane_parts(bar, factor))
+                .collect();
+        }
+        if let LayerMode::Polymeter {
+            length: Some(length),
+        } = &mut self.layer_mode
+        {
+            *length = length.saturating_mul(factor).min(MAX_POLYMETER_BARS);
+        }
+        self
+    }
+
+    /// Squeezes every `factor` bars into one, padding the last with rests.
+    /// Polymeter layers are padded separately, so their lengths may change.
+    /// Bars in different meters keep their proportions, and the new bar
+    /// takes the meter of the first.
+    pub fn diminish(&mut self, factor: usize) -> &mut Self {
+        let factor = factor.clamp(1, MAX_POLYMETER_BARS);
+        let bar_count = self.bar_count();
+        let meters = self.meters();
+        let track_meter = self.meter;
+        let sixteenths = |bar: &Bar| bar.meter.unwrap_or(track_meter).sixteenths();
+        if let LayerMode::Polymeter {
+            length: Some(length),
+        } = &mut self.layer_mode
+        {
+            *length = length.div_ceil(factor);
+        }
+        for layer in &mut self.layers {
+            layer.bars = layer
+                .bars
+                .chunks(factor)
+                .map(|chunk| {
+                    let mut divisions: Vec<Division> = chunk
+                        .iter()
+                        .map(|bar| Division {
+                            item: match bar.divisions.as_slice() {
+                                [single] => single.item.clone(),
+                                divisions => Item::Sequence(divisions.to_vec()),
+                            },
+                            weight: sixteenths(bar),
+                        })
+                        .collect();
+                    if chunk.len() < factor {
+                        let last = sixteenths(&chunk[chunk.len() - 1]);
+                        divisions.push(rest((factor - chunk.len()) * last));
+                    }
+                    let unit = divisions.iter().fold(0, |a, d| gcd(a, d.weight));
+                    for division in &mut divisions {
+                        division.weight /= unit;
+                    }
+                    Bar {
+                        divisions,
+                        meter: chunk[0].meter,
+                    }
+                })
+                .collect();
+        }
+        // Lanes repeat to fill the track, so write that out before grouping.
+        for lane in &mut self.lanes {
+            if lane.bars.is_empty() {
+                continue;
+            }
+            let bars: Vec<_> = lane.bars.iter().cycle().take(bar_count).cloned().collect();
+            lane.bars = bars
+                .chunks(factor)
+                .zip(meters.chunks(factor))
+                .map(|(chunk, meters)| {
+                    let mut divisions: Vec<LaneDivision> = chunk
+                        .iter()
+                        .zip(meters)
+                        .map(|(bar, meter)| LaneDivision {
+                            item: match bar.as_slice() {
+                                [single] => single.item.clone(),
+                                divisions => LaneItem::Sequence(divisions.to_vec()),
+                            },
+                            weight: meter.sixteenths(),
+                        })
+                        .collect();
+                    if chunk.len() < factor {
+                        let last = meters[meters.len() - 1].sixteenths();
+                        divisions.push(LaneDivision {
+                            item: LaneItem::Hold,
+                            weight: (factor - chunk.len()) * last,
+                        });
+                    }
+                    let unit = divisions.iter().fold(0, |a, d| gcd(a, d.weight));
+                    for division in &mut divisions {
+                        division.weight /= unit;
+                    }
+                    divisions
+                })
+                .collect();
+        }
+        self
+    }
+
+    fn for_each_division(&mut self, f: &mut impl FnMut(&mut Division)) {
+        for bar in self.layers.iter_mut().flat_map(|l| &mut l.bars) {
+            bar.divisions.iter_mut().for_each(&mut *f);
+        }
+    }
+}
+
+fn rest(weight: usize) -> Division {
+    Division {
+        item: Item::Rest,
+        weight,
+    }
+}
+
+fn for_each_child(item: &mut Item, f: &mut impl FnMut(&mut Item)) {
+    match item {
+        Item::Sequence(divisions) => divisions.iter_mut().for_each(|d| f(&mut d.item)),
+        Item::Polyphony(layers) | Item::Choice(layers) => {
+            layers.iter_mut().flatten().for_each(|d| f(&mut d.item))
+        }
+        Item::Shift(inner, _) | Item::Chance(inner, _) => f(inner),
+        Item::Note(_) | Item::Chord(_) | Item::Rest => {}
+    }
+}
+
+/// Folds a shift directly inside another shift into it.
+fn merge_shifts(item: &mut Item) {
+    if let Item::Shift(inner, shift) = item
+        && let Item::Shift(innermost, inner_shift) = inner.as_mut()
+    {
+        shift.octaves += inner_shift.octaves;
+        shift.degrees += inner_shift.degrees;
+        let innermost = std::mem::replace(innermost.as_mut(), Item::Rest);
+        **inner = innermost;
+    }
+}
+
+fn transpose_item(item: &mut Item, degrees: i32) {
+    match item {
+        Item::Note(note) => note.degree += degrees,
+        Item::Chord(chord) => {
+            // Roman numerals only go up to VII, and letter chords have no
+            // degree, so anything else is transposed as a group.
+            if let ChordRoot::Roman { degree, .. } = &mut chord.root
+                && (0..7).contains(&(*degree + degrees))
+            {
+                *degree += degrees;
+            } else if degrees != 0 {
+                let chord = std::mem::replace(item, Item::Rest);
+                *item = Item::Shift(
+                    Box::new(chord),
+                    Shift {
+                        octaves: 0,
+                        degrees,
+                    },
+                );
+            }
+        }
+        _ => {
+            for_each_child(item, &mut |child| transpose_item(child, degrees));
+            merge_shifts(item);
+        }
+    }
+}
+
+fn invert_item(item: &mut Item, axis: i32) {
+    match item {
+        Item::Note(note) => {
+            note.degree = 2 * axis - note.degree;
+            note.chromatic_shift = -note.chromatic_shift;
+        }
+        Item::Chord(chord) => {
+            if let ChordRoot::Roman { degree, .. } = chord.root {
+                transpose_item(item, 2 * (axis - degree));
+            }
+        }
+        _ => {
+            if let Item::Shift(_, shift) = item {
+                shift.octaves = -shift.octaves;
+                shift.degrees = -shift.degrees;
+            }
+            for_each_child(item, &mut |child| invert_item(child, axis));
+            merge_shifts(item);
+        }
+    }
+}
+
+fn reverse_divisions(divisions: &mut [Division]) {
+    divisions.reverse();
+    for div in divisions {
+        reverse_item(&mut div.item);
+    }
+}
+
+fn reverse_item(item: &mut Item) {
+    match item {
+        Item::Sequence(divisions) => reverse_divisions(divisions),
+        Item::Polyphony(layers) | Item::Choice(layers) => {
+            layers.iter_mut().for_each(|l| reverse_divisions(l))
+        }
+        Item::Shift(inner, _) | Item::Chance(inner, _) => reverse_item(inner),
+        Item::Note(_) | Item::Chord(_) | Item::Rest => {}
+    }
+}
+
+fn reduce_weights(weights: impl Iterator<Item = usize>) -> usize {
+    weights.fold(0, gcd).max(1)
+}
+
+/// Splits divisions into `parts` sequences of equal length.
+fn split_parts(divisions: &[Division], parts: usize) -> Vec<Vec<Division>> {
+    let mut result = Vec::new();
+    let mut rest = divisions.to_vec();
+    for remaining in (2..=parts).rev() {
+        let (first, second) = split_divisions(&rest, 1, remaining);
+        result.push(first);
+        rest = second;
+    }
+    result.push(rest);
+    result
+}
+
+/// Splits divisions lasting `len` units at `at` units, where `0 < at < len`.
+fn split_divisions(
+    divisions: &[Division],
+    at: usize,
+    len: usize,
+) -> (Vec<Division>, Vec<Division>) {
+    // Scaling the weights by `len` puts the split on a whole weight unit.
+    let total: usize = divisions.iter().map(|d| d.weight).sum();
+    let boundary = at * total;
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    let mut pos = 0;
+    for div in divisions {
+        let end = pos + div.weight * len;
+        if end <= boundary {
+            first.push(Division {
+                item: div.item.clone(),
+                weight: end - pos,
+            });
+        } else if pos >= boundary {
+            second.push(Division {
+                item: div.item.clone(),
+                weight: end - pos,
+            });
+        } else {
+            let (a, b) = split_item(&div.item, boundary - pos, end - pos);
+            first.push(Division {
+                item: a,
+                weight: boundary - pos,
+            });
+            second.push(Division {
+                item: b,
+                weight: end - boundary,
+            });
+        }
+        pos = end;
+    }
+    for part in [&mut first, &mut second] {
+        let common = reduce_weights(part.iter().map(|d| d.weight));
+        part.iter_mut().for_each(|d| d.weight /= common);
+    }
+    (first, second)
+}
+
+fn split_item(item: &Item, at: usize, len: usize) -> (Item, Item) {
+    let split_all = |options: &[Vec<Division>]| -> (Vec<_>, Vec<_>) {
+        options
+            .iter()
+            .map(|divisions| split_divisions(divisions, at, len))
+            .unzip()
+    };
+    match item {
+        Item::Note(note) => (
+            Item::Note(ParsedNote {
+                tie: true,
+                slur: false,
+                ..note.clone()
+            }),
+            item.clone(),
+        ),
+        Item::Chord(chord) => (
+            Item::Chord(Chord {
+                tie: true,
+                slur: false,
+                ..chord.clone()
+            }),
+            item.clone(),
+        ),
+        Item::Rest => (Item::Rest, Item::Rest),
+        Item::Sequence(divisions) => {
+            let (a, b) = split_divisions(divisions, at, len);
+            (Item::Sequence(a), Item::Sequence(b))
+        }
+        Item::Polyphony(layers) => {
+            let (a, b) = split_all(layers);
+            (Item::Polyphony(a), Item::Polyphony(b))
+        }
+        Item::Choice(options) => {
+            let (a, b) = split_all(options);
+            (Item::Choice(a), Item::Choice(b))
+        }
+        Item::Shift(inner, shift) => {
+            let (a, b) = split_item(inner, at, len);
+            (
+                Item::Shift(Box::new(a), *shift),
+                Item::Shift(Box::new(b), *shift),
+            )
+        }
+        Item::Chance(inner, percent) => {
+            let (a, b) = split_item(inner, at, len);
+            (
+                Item::Chance(Box::new(a), *percent),
+                Item::Chance(Box::new(b), *percent),
+            )
+        }
+    }
+}
+
+fn split_lane_parts(divisions: &[LaneDivision], parts: usize) -> Vec<Vec<LaneDivision>> {
+    let mut result = Vec::new();
+    let mut rest = divisions.to_vec();
+    for remaining in (2..=parts).rev() {
+        let (first, second) = split_lane_divisions(&rest, 1, remaining);
+        result.push(first);
+        rest = second;
+    }
+    result.push(rest);
+    result
+}
+
+fn split_lane_divisions(
+    divisions: &[LaneDivision],
+    at: usize,
+    len: usize,
+) -> (Vec<LaneDivision>, Vec<LaneDivision>) {
+    let total: usize = divisions.iter().map(|d| d.weight).sum();
+    let boundary = at * total;
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    let mut pos = 0;
+    for div in divisions {
+        let end = pos + div.weight * len;
+        if end <= boundary {
+            first.push(LaneDivision {
+                item: div.item.clone(),
+                weight: end - pos,
+            });
+        } else if pos >= boundary {
+            second.push(LaneDivision {
+                item: div.item.clone(),
+                weight: end - pos,
+            });
+        } else {
+            let (a, b) = match &div.item {
+                LaneItem::Sequence(inner) => {
+                    let (a, b) = split_lane_divisions(inner, boundary - pos, end - pos);
+                    (LaneItem::Sequence(a), LaneItem::Sequence(b))
+                }
+                // The value carries on past the bar line.
+                item => (item.clone(), LaneItem::Hold),
+            };
+            first.push(LaneDivision {
+                item: a,
+                weight: boundary - pos,
+            });
+            second.push(LaneDivision {
+                item: b,
+                weight: end - boundary,
+            });
+        }
+        pos = end;
+    }
+    for part in [&mut first, &mut second] {
+        let common = reduce_weights(part.iter().map(|d| d.weight));
+        part.iter_mut().for_each(|d| d.weight /= common);
+    }
+    (first, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Track;
+
+    fn canonical(notation: &str) -> String {
+        let ast: ParsedTrackAST = notation.parse().unwrap();
+        let printed = ast.to_string();
+        let reparsed: ParsedTrackAST = printed.parse().unwrap();
+        assert_eq!(reparsed, ast, "{} printed as {}", notation, printed);
+        printed
+    }
+
+    #[test]
+    fn test_format_canonical_notation() {
+        assert_eq!(canonical("( 0 / _ / 2 )"), "(0/_/2)");
+        assert_eq!(canonical("(0*/1+!/2^64~/-3.=)"), "(0*/1+!/2^64~/-3.=)");
+        assert_eq!(canonical("(0)x3 (1) (1)"), "(0)x3(1)x2");
+        assert_eq!(canonical("A = (0)\nA (2)"), "(0)(2)");
+        assert_eq!(canonical("{(0/1)&(2/3)}"), "({0/1&2/3})");
+        assert_eq!(canonical("(0/>1/2/<3)"), "(0/'1/'2/3)");
+        assert_eq!(canonical("((0/2)+3/,4)(0)-1"), "((0/2)+3/,4)((0)-1)");
+        assert_eq!(canonical("(0?/[1|2/3]?30)"), "(0?50/[1|2/3]?30)");
+        assert_eq!(canonical("(e(3,8))"), "((0/_*/0/_*/0/_))");
+        assert_eq!(canonical("(0++/1--)"), "(0++/1--)");
+    }
+
+    #[test]
+    fn test_format_chords() {
+        assert_eq!(canonical("(Cm7/F7/Bbmaj7)"), "(Cm7/F7/A#maj7)");
+        assert_eq!(canonical("(C/Gmaj/G7/B*/C@1%!)"), "(C/Gmaj/G7/B*/C@1%!)");
+        assert_eq!(canonical("(bVII/iv7/'I)"), "(bVII/iv7/'I)");
+    }
+
+    #[test]
+    fn test_format_lanes() {
+        assert_eq!(
+            canonical("(0)(1)\ncutoff: ramp (0.5/1*)x2\nres:(0/(-0.25/_))"),
+            "(0)(1)\ncutoff: ramp (0.5/1*)x2\nres: (0/(-0.25/_))"
+        );
+    }
+
+    #[test]
+    fn test_format_layers() {
+        assert_eq!(
+            canonical("polymeter 12\n(0)(1) &(2)x3\namp: (1)"),
+            "polymeter 12\n(0)(1)\n& (2)x3\namp: (1)"
+        );
+        assert_eq!(canonical("&(0)\n&(1/2)"), "(0)\n& (1/2)");
+    }
+
+    #[test]
+    fn test_format_meters() {
+        assert_eq!(
+            canonical("meter 3/4\n(0)(0) 7/8(1)\n&5/8(2)"),
+            "meter 3/4\n(0)x2 7/8(1)\n& 5/8(2)"
+        );
+        assert_eq!(
+            transformed("7/8(0/1)", |a| {
+                a.augment(2);
+            }),
+            "7/8(0) 7/8(1)"
+        );
+        assert_eq!(
+            transformed("3/4(0)(1/2)(3)", |a| {
+                a.diminish(2);
+            }),
+            "3/4(0**/(1/2)***)(3/_)"
+        );
+        assert_eq!(
+            transformed("3/4(0)(1/2)(3)\namp: (0.5)(1)(0)", |a| {
+                a.diminish(2);
+            }),
+            "3/4(0**/(1/2)***)(3/_)\namp: (0.5**/1***)(0/_)"
+        );
+    }
+
+    fn transformed(notation: &str, transform: impl Fn(&mut ParsedTrackAST)) -> String {
+        let mut ast: ParsedTrackAST = notation.parse().unwrap();
+        transform(&mut ast);
+        canonical(&ast.to_string())
+    }
+
+    #[test]
+    fn test_pitch_transforms() {
+        assert_eq!(
+            transformed("(0/2/4)", |a| {
+                a.transpose(3);
+            }),
+            "(3/5/7)"
+        );
+        assert_eq!(
+            transformed("(C/IV/VII/'(0))", |a| {
+                a.transpose(1);
+            }),
+            "((C)+1/V/(VII)+1/'(1))"
+        );
+        assert_eq!(
+            transformed("(0/1+/'4/II)", |a| {
+                a.invert(2);
+            }),
+            "(4/3-/,0/IV)"
+        );
+        assert_eq!(
+            transformed("(0/1)\namp: (1/2)", |a| {
+                a.transpose(-1).invert(0);
+            }),
+            "(1/0)\namp: (1/2)"
+        );
+    }
+
+    #[test]
+    fn test_time_transforms() {
+        assert_eq!(
+            transformed("(0/(1/2))(3)", |a| {
+                a.reverse();
+            }),
+            "(3)((2/1)/0)"
+        );
+        assert_eq!(
+            transformed("(0/1*/2)", |a| {
+                a.rotate(1);
+            }),
+            "(1*/2/0)"
+        );
+        assert_eq!(
+            transformed("(0/1*/2)", |a| {
+                a.rotate(-1);
+            }),
+            "(2/0/1*)"
+        );
+        assert_eq!(
+            transformed("(0/1/2/3)\namp: (0.5/1)", |a| {
+                a.augment(2);
+            }),
+            "(0/1)(2/3)\namp: (0.5)(1)"
+        );
+        assert_eq!(
+            transformed("(0**/1)", |a| {
+                a.augment(2);
+            }),
+            "(0~)(0/1)"
+        );
+        assert_eq!(
+            transformed("(0)(1/2)(3)\namp: (0.5)", |a| {
+                a.diminish(2);
+            }),
+            "(0/(1/2))(3/_)\namp: (0.5/0.5)(0.5/_)"
+        );
+    }
+
+    #[test]
+    fn test_huge_factors_stay_bounded() {
+        let mut ast: ParsedTrackAST = "polymeter 3\n(0)".parse().unwrap();
+        ast.augment(usize::MAX);
+        assert_eq!(
+            ast.layer_mode,
+            LayerMode::Polymeter {
+                length: Some(MAX_POLYMETER_BARS)
+            }
+        );
+        let mut ast: ParsedTrackAST = "(0)(1)\namp: (1)".parse().unwrap();
+        ast.diminish(usize::MAX);
+        assert_eq!((ast.bar_count(), ast.lanes[0].bars.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_augmented_track_keeps_timing() {
+        let scale = crate::scale::cmaj();
+        let mut ast: ParsedTrackAST = "((0/1/2)/3**)".parse().unwrap();
+        ast.augment(3);
+        let track = Track::from_ast(&ast, &scale);
+        assert_eq!(track.bar_count(), 3);
+        let spans: Vec<_> = track
+            .notes()
+            .iter()
+            .map(|n| (n.pitch, (n.start * 24.0).round(), (n.end * 24.0).round()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (48, 0.0, 2.0),
+                (50, 2.0, 4.0),
+                (52, 4.0, 6.0),
+                (53, 6.0, 24.0)
+            ]
+        );
+    }
+}
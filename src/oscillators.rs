@@ -1,4 +1,4 @@
-use crate::Signal;
+use crate::{Signal, utils::next_random};
 
 #[derive(Clone, Copy)]
 pub enum Wave {
@@ -113,6 +113,11 @@ impl Osc {
         self
     }
 
+    pub fn seed(&mut self, seed: u32) -> &mut Self {
+        self.noise_seed = seed;
+        self
+    }
+
     pub fn unipolar(&mut self) -> &mut Self {
         self.unipolar = true;
         self
@@ -189,11 +194,7 @@ impl Osc {
                 }
             }
             Wave::WhiteNoise => {
-                self.noise_seed = self
-                    .noise_seed
-                    .wrapping_mul(196314165)
-                    .wrapping_add(907633515);
-                let bipolar_sample = (self.noise_seed as f32 / u32::MAX as f32) * 2.0 - 1.0;
+                let bipolar_sample = next_random(&mut self.noise_seed) * 2.0 - 1.0;
                 let scaled = bipolar_sample * 0.25;
                 if self.unipolar {
                     (scaled + 1.0) * 0.5
@@ -206,6 +207,11 @@ impl Osc {
         self.computed_sample = sample * self.attenuation;
     }
 
+    pub fn reset(&mut self) {
+        self.phase_accumulator = 0;
+        self.computed_sample = 0.0;
+    }
+
     pub fn copy_phase_from(&mut self, other: &Osc) {
         self.phase_accumulator = other.phase_accumulator;
         self.noise_seed = other.noise_seed;
@@ -0,0 +1,280 @@
+const MAX_STAGES: usize = 3;
+const MAX_RATIO: usize = 1 << MAX_STAGES;
+
+const HIIR_8: [f64; 4] = [
+    0.07711507983241622,
+    0.22823651466538192,
+    0.421_978_042_944_980_2,
+    0.690_592_758_682_615_8,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OversampleFactor {
+    #[default]
+    X2,
+    X4,
+    X8,
+}
+
+impl OversampleFactor {
+    pub fn ratio(self) -> usize {
+        1 << self.stages()
+    }
+
+    fn stages(self) -> usize {
+        match self {
+            OversampleFactor::X2 => 1,
+            OversampleFactor::X4 => 2,
+            OversampleFactor::X8 => 3,
+        }
+    }
+}
+
+struct AllpassSection {
+    a: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl AllpassSection {
+    fn new(coefficient: f64) -> Self {
+        Self {
+            a: coefficient,
+            x1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let y = self.x1 + (input - self.y1) * self.a;
+        self.x1 = input;
+        self.y1 = y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+
+    fn copy_state_from(&mut self, other: &AllpassSection) {
+        self.x1 = other.x1;
+        self.y1 = other.y1;
+    }
+}
+
+struct HalfbandFilter {
+    even: Vec<AllpassSection>,
+    odd: Vec<AllpassSection>,
+}
+
+impl HalfbandFilter {
+    fn new(coeffs: &[f64]) -> Self {
+        let even: Vec<_> = coeffs
+            .iter()
+            .step_by(2)
+            .map(|&c| AllpassSection::new(c))
+            .collect();
+        let odd: Vec<_> = coeffs
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|&c| AllpassSection::new(c))
+            .collect();
+        Self { even, odd }
+    }
+
+    fn upsample(&mut self, input: f64) -> (f64, f64) {
+        let mut even_out = input;
+        for section in &mut self.even {
+            even_out = section.process(even_out);
+        }
+
+        let mut odd_out = input;
+        for section in &mut self.odd {
+            odd_out = section.process(odd_out);
+        }
+
+        (even_out, odd_out)
+    }
+
+    fn downsample(&mut self, in0: f64, in1: f64) -> f64 {
+        let mut even_out = in1;
+        for section in &mut self.even {
+            even_out = section.process(even_out);
+        }
+
+        let mut odd_out = in0;
+        for section in &mut self.odd {
+            odd_out = section.process(odd_out);
+        }
+
+        (even_out + odd_out) * 0.5
+    }
+
+    fn reset(&mut self) {
+        for s in &mut self.even {
+            s.reset();
+        }
+        for s in &mut self.odd {
+            s.reset();
+        }
+    }
+
+    fn copy_state_from(&mut self, other: &HalfbandFilter) {
+        for (s, o) in self.even.iter_mut().zip(&other.even) {
+            s.copy_state_from(o);
+        }
+        for (s, o) in self.odd.iter_mut().zip(&other.odd) {
+            s.copy_state_from(o);
+        }
+    }
+}
+
+pub struct Oversampler {
+    factor: OversampleFactor,
+    up: Vec<HalfbandFilter>,
+    down: Vec<HalfbandFilter>,
+    buffer: [f64; MAX_RATIO],
+}
+
+impl Default for Oversampler {
+    fn default() -> Self {
+        Self {
+            factor: OversampleFactor::X2,
+            up: (0..MAX_STAGES)
+                .map(|_| HalfbandFilter::new(&HIIR_8))
+                .collect(),
+            down: (0..MAX_STAGES)
+                .map(|_| HalfbandFilter::new(&HIIR_8))
+                .collect(),
+            buffer: [0.0; MAX_RATIO],
+        }
+    }
+}
+
+impl Oversampler {
+    pub fn factor(&mut self, factor: OversampleFactor) -> &mut Self {
+        if factor != self.factor {
+            self.factor = factor;
+            self.reset();
+        }
+        self
+    }
+
+    pub fn current_factor(&self) -> OversampleFactor {
+        self.factor
+    }
+
+    pub fn process(&mut self, input: f32, mut f: impl FnMut(f32) -> f32) -> f32 {
+        let stages = self.factor.stages();
+        let mut len = 1;
+        self.buffer[0] = input as f64;
+
+        for stage in &mut self.up[..stages] {
+            let prev = self.buffer;
+            for (i, &x) in prev[..len].iter().enumerate() {
+                let (a, b) = stage.upsample(x);
+                self.buffer[i * 2] = a;
+                self.buffer[i * 2 + 1] = b;
+            }
+            len *= 2;
+        }
+
+        for x in &mut self.buffer[..len] {
+            *x = f(*x as f32) as f64;
+        }
+
+        for stage in self.down[..stages].iter_mut().rev() {
+            len /= 2;
+            for i in 0..len {
+                self.buffer[i] = stage.downsample(self.buffer[i * 2], self.buffer[i * 2 + 1]);
+            }
+        }
+
+        self.buffer[0] as f32
+    }
+
+    pub fn reset(&mut self) {
+        for stage in self.up.iter_mut().chain(self.down.iter_mut()) {
+            stage.reset();
+        }
+    }
+
+    pub fn copy_state_from(&mut self, other: &Oversampler) {
+        for (s, o) in self.up.iter_mut().zip(&other.up) {
+            s.copy_state_from(o);
+        }
+        for (s, o) in self.down.iter_mut().zip(&other.down) {
+            s.copy_state_from(o);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine_peak(factor: OversampleFactor, freq: f32) -> f32 {
+        let mut oversampler = Oversampler::default();
+        oversampler.factor(factor);
+        let mut peak = 0.0f32;
+        for i in 0..44100 {
+            let x = (i as f32 * freq * std::f32::consts::TAU / 44100.0).sin();
+            let y = oversampler.process(x, |s| s);
+            if i > 22050 {
+                peak = peak.max(y.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_identity_is_unity_gain() {
+        for factor in [
+            OversampleFactor::X2,
+            OversampleFactor::X4,
+            OversampleFactor::X8,
+        ] {
+            for freq in [100.0, 1000.0, 10000.0] {
+                let peak = sine_peak(factor, freq);
+                assert!(
+                    (peak - 1.0).abs() < 0.02,
+                    "{:?} at {}Hz has peak {}",
+                    factor,
+                    freq,
+                    peak
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_closure_runs_at_oversampled_rate() {
+        for factor in [
+            OversampleFactor::X2,
+            OversampleFactor::X4,
+            OversampleFactor::X8,
+        ] {
+            let mut oversampler = Oversampler::default();
+            oversampler.factor(factor);
+            let mut calls = 0;
+            oversampler.process(0.5, |x| {
+                calls += 1;
+                x
+            });
+            assert_eq!(calls, factor.ratio());
+        }
+    }
+
+    #[test]
+    fn test_reset_clears_state() {
+        let mut oversampler = Oversampler::default();
+        oversampler.factor(OversampleFactor::X4);
+        for _ in 0..100 {
+            oversampler.process(1.0, |x| x);
+        }
+        oversampler.reset();
+        assert_eq!(oversampler.process(0.0, |x| x), 0.0);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{delay::Delay, signal::Signal};
+
+const MAX_WINDOW_SECONDS: f32 = 0.2;
+const MAX_SAMPLE_RATE: f32 = 96000.0;
+
+pub struct PitchShifter {
+    delay: Delay,
+    semitones: f32,
+    cents: f32,
+    window: f32,
+    phase: f32,
+}
+
+impl Default for PitchShifter {
+    fn default() -> Self {
+        Self {
+            delay: Delay::new((MAX_WINDOW_SECONDS * MAX_SAMPLE_RATE) as usize + 2),
+            semitones: 0.0,
+            cents: 0.0,
+            window: 0.05,
+            phase: 0.0,
+        }
+    }
+}
+
+impl PitchShifter {
+    pub fn semitones(&mut self, semitones: f32) -> &mut Self {
+        self.semitones = semitones.clamp(-48.0, 48.0);
+        self
+    }
+
+    pub fn cents(&mut self, cents: f32) -> &mut Self {
+        self.cents = cents.clamp(-100.0, 100.0);
+        self
+    }
+
+    pub fn window(&mut self, seconds: f32) -> &mut Self {
+        self.window = seconds.clamp(0.005, MAX_WINDOW_SECONDS);
+        self
+    }
+
+    pub fn ratio(&self) -> f32 {
+        2.0f32.powf((self.semitones + self.cents / 100.0) / 12.0)
+    }
+
+    pub fn output(&mut self, input: f32, signal: &mut Signal) -> f32 {
+        let window =
+            (self.window * signal.sample_rate as f32).min(MAX_WINDOW_SECONDS * MAX_SAMPLE_RATE);
+        let second_phase = (self.phase + 0.5).fract();
+
+        let first = self.delay.read(1.0 + self.phase * window);
+        let second = self.delay.read(1.0 + second_phase * window);
+        self.delay.write(input);
+
+        let first_gain = 0.5 - 0.5 * (self.phase * std::f32::consts::TAU).cos();
+        let second_gain = 1.0 - first_gain;
+
+        self.phase = (self.phase + (1.0 - self.ratio()) / window).rem_euclid(1.0);
+
+        first * first_gain + second * second_gain
+    }
+
+    pub fn reset(&mut self) {
+        self.delay.reset();
+        self.phase = 0.0;
+    }
+
+    pub fn copy_state_from(&mut self, other: &PitchShifter) {
+        self.delay.copy_state_from(&other.delay);
+        self.phase = other.phase;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shifted(semitones: f32, cents: f32) -> Vec<f32> {
+        let mut shifter = PitchShifter::default();
+        shifter.semitones(semitones).cents(cents).window(0.03);
+        let mut signal = Signal::new(44100);
+
+        (0..44100)
+            .map(|i| {
+                let input = (i as f32 * 440.0 * std::f32::consts::TAU / 44100.0).sin();
+                shifter.output(input, &mut signal)
+            })
+            .collect()
+    }
+
+    fn power_at(samples: &[f32], freq: f32) -> f32 {
+        samples
+            .chunks_exact(1024)
+            .map(|block| {
+                let (mut re, mut im) = (0.0, 0.0);
+                for (i, &x) in block.iter().enumerate() {
+                    let angle = i as f32 * freq * std::f32::consts::TAU / 44100.0;
+                    re += x * angle.cos();
+                    im += x * angle.sin();
+                }
+                re * re + im * im
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_unity_ratio_passes_pitch() {
+        let output = shifted(0.0, 0.0);
+        assert!(power_at(&output[4410..], 440.0) > power_at(&output[4410..], 660.0) * 100.0);
+    }
+
+    #[test]
+    fn test_octave_up_and_down() {
+        let up = shifted(12.0, 0.0);
+        assert!(power_at(&up[4410..], 880.0) > power_at(&up[4410..], 440.0) * 10.0);
+
+        let down = shifted(-12.0, 0.0);
+        assert!(power_at(&down[4410..], 220.0) > power_at(&down[4410..], 440.0) * 10.0);
+    }
+
+    #[test]
+    fn test_cents_combine_with_semitones() {
+        let mut shifter = PitchShifter::default();
+        shifter.semitones(7.0).cents(-50.0);
+        assert!((shifter.ratio() - 2.0f32.powf(6.5 / 12.0)).abs() < 1e-6);
+    }
+}
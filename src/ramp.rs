@@ -66,6 +66,13 @@ impl Ramp {
         *current_value
     }
 
+    pub fn reset(&mut self) {
+        self.current_value = None;
+        self.target_value = 0.0;
+        self.start_value = 0.0;
+        self.start_time = None;
+    }
+
     pub fn copy_state_from(&mut self, other: &Ramp) {
         self.current_value = other.current_value;
         self.target_value = other.target_value;
@@ -114,6 +114,18 @@ impl Reverb {
         (left + right) * 0.25
     }
 
+    pub fn reset(&mut self) {
+        for diffuser in &mut self.input_diffusers {
+            diffuser.buffer.fill(0.0);
+        }
+        for delay in &mut self.tank_delays {
+            delay.buffer.fill(0.0);
+        }
+        for filter in &mut self.tank_damping {
+            filter.state = 0.0;
+        }
+    }
+
     pub fn copy_state_from(&mut self, other: &Reverb) {
         for (new_d, old_d) in self
             .input_diffusers
@@ -0,0 +1,243 @@
+use std::f64::consts::PI;
+
+const SINC_ZERO_CROSSINGS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SampleBuffer {
+    sample_rate: u32,
+    source_rate: u32,
+    source_channels: usize,
+    left: Vec<f32>,
+    right: Option<Vec<f32>>,
+}
+
+impl Default for SampleBuffer {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            source_rate: 44100,
+            source_channels: 1,
+            left: Vec::new(),
+            right: None,
+        }
+    }
+}
+
+impl SampleBuffer {
+    pub fn mono(samples: Vec<f32>, sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            source_rate: sample_rate,
+            source_channels: 1,
+            left: samples,
+            right: None,
+        }
+    }
+
+    pub fn stereo(left: Vec<f32>, right: Vec<f32>, sample_rate: u32) -> Self {
+        assert_eq!(left.len(), right.len());
+        Self {
+            sample_rate,
+            source_rate: sample_rate,
+            source_channels: 2,
+            left,
+            right: Some(right),
+        }
+    }
+
+    /// Channels beyond the first two are dropped.
+    pub fn from_interleaved(data: &[f32], channels: usize, sample_rate: u32) -> Self {
+        let channels = channels.max(1);
+        let frames = data.chunks_exact(channels);
+        let mut buffer = if channels == 1 {
+            Self::mono(data.to_vec(), sample_rate)
+        } else {
+            let left = frames.clone().map(|frame| frame[0]).collect();
+            let right = frames.map(|frame| frame[1]).collect();
+            Self::stereo(left, right, sample_rate)
+        };
+        buffer.source_channels = channels;
+        buffer
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn source_rate(&self) -> u32 {
+        self.source_rate
+    }
+
+    pub fn source_channels(&self) -> usize {
+        self.source_channels
+    }
+
+    pub fn is_stereo(&self) -> bool {
+        self.right.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.len() as f32 / self.sample_rate as f32
+    }
+
+    pub fn left(&self) -> &[f32] {
+        &self.left
+    }
+
+    pub fn right(&self) -> &[f32] {
+        self.right.as_deref().unwrap_or(&self.left)
+    }
+
+    /// Reads a stereo frame at a fractional frame index with linear interpolation.
+    /// Mono buffers return the same value on both sides.
+    pub fn read(&self, index: f32) -> (f32, f32) {
+        if self.is_empty() {
+            return (0.0, 0.0);
+        }
+        let index = index.clamp(0.0, (self.len() - 1) as f32);
+        let i = index as usize;
+        let frac = index - i as f32;
+        let lerp = |samples: &[f32]| {
+            let s0 = samples[i];
+            let s1 = samples.get(i + 1).copied().unwrap_or(s0);
+            s0 + frac * (s1 - s0)
+        };
+        let left = lerp(&self.left);
+        let right = self.right.as_deref().map(lerp).unwrap_or(left);
+        (left, right)
+    }
+
+    /// Converts to `target_rate` with a Blackman-windowed sinc interpolator.
+    /// The filter cutoff follows the lower of the two Nyquist frequencies.
+    pub fn resample(&self, target_rate: u32) -> SampleBuffer {
+        if target_rate == self.sample_rate || self.is_empty() {
+            let mut buffer = self.clone();
+            buffer.sample_rate = target_rate;
+            return buffer;
+        }
+
+        let step = self.sample_rate as f64 / target_rate as f64;
+        let cutoff = (1.0 / step).min(1.0);
+        let half_width = (SINC_ZERO_CROSSINGS as f64 / cutoff).ceil() as isize;
+        let length = (self.len() as f64 / step).ceil() as usize;
+
+        Self {
+            sample_rate: target_rate,
+            source_rate: self.source_rate,
+            source_channels: self.source_channels,
+            left: resample_channel(&self.left, length, step, cutoff, half_width),
+            right: self
+                .right
+                .as_ref()
+                .map(|right| resample_channel(right, length, step, cutoff, half_width)),
+        }
+    }
+}
+
+fn resample_channel(
+    input: &[f32],
+    length: usize,
+    step: f64,
+    cutoff: f64,
+    half_width: isize,
+) -> Vec<f32> {
+    (0..length)
+        .map(|n| {
+            let t = n as f64 * step;
+            let center = t.floor() as isize;
+            let start = (center - half_width + 1).max(0);
+            let end = (center + half_width).min(input.len() as isize - 1);
+            let mut sum = 0.0;
+            for k in start..=end {
+                let x = t - k as f64;
+                sum += input[k as usize] as f64 * windowed_sinc(x, cutoff, half_width as f64);
+            }
+            sum as f32
+        })
+        .collect()
+}
+
+fn windowed_sinc(x: f64, cutoff: f64, half_width: f64) -> f64 {
+    let ratio = x / half_width;
+    if ratio.abs() >= 1.0 {
+        return 0.0;
+    }
+    let window = 0.42 + 0.5 * (PI * ratio).cos() + 0.08 * (2.0 * PI * ratio).cos();
+    let arg = cutoff * x;
+    let sinc = if arg.abs() < 1e-9 {
+        1.0
+    } else {
+        (PI * arg).sin() / (PI * arg)
+    };
+    cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, freq: f64, sample_rate: u32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f64 * freq * 2.0 * PI / sample_rate as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_from_interleaved_keeps_stereo() {
+        let buffer = SampleBuffer::from_interleaved(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], 2, 48000);
+        assert!(buffer.is_stereo());
+        assert_eq!(buffer.left(), &[0.1, 0.3, 0.5]);
+        assert_eq!(buffer.right(), &[0.2, 0.4, 0.6]);
+        assert_eq!(buffer.source_channels(), 2);
+        assert_eq!(buffer.sample_rate(), 48000);
+    }
+
+    #[test]
+    fn test_resample_preserves_pitch_and_duration() {
+        let buffer = SampleBuffer::mono(sine(48000, 1000.0, 48000), 48000);
+        let resampled = buffer.resample(44100);
+
+        assert_eq!(resampled.sample_rate(), 44100);
+        assert_eq!(resampled.source_rate(), 48000);
+        assert!((resampled.duration() - buffer.duration()).abs() < 1e-3);
+
+        let expected = sine(resampled.len(), 1000.0, 44100);
+        for (i, (&got, &want)) in resampled.left().iter().zip(&expected).enumerate() {
+            if i > 100 && i < resampled.len() - 100 {
+                assert!(
+                    (got - want).abs() < 1e-3,
+                    "sample {}: {} vs {}",
+                    i,
+                    got,
+                    want
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_downsampling_rejects_content_above_nyquist() {
+        let buffer = SampleBuffer::mono(sine(96000, 30000.0, 96000), 96000);
+        let resampled = buffer.resample(44100);
+
+        let peak = resampled.left()[200..resampled.len() - 200]
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak < 0.01, "aliased peak {}", peak);
+    }
+
+    #[test]
+    fn test_read_interpolates_frames() {
+        let buffer = SampleBuffer::stereo(vec![0.0, 1.0], vec![1.0, 0.0], 44100);
+        assert_eq!(buffer.read(0.25), (0.25, 0.75));
+        assert_eq!(buffer.read(5.0), (1.0, 0.0));
+    }
+}
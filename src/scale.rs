@@ -1,189 +1,347 @@
+use std::fmt;
+use std::str::FromStr;
+
+const MAX_SCALE_NOTES: usize = 32;
+const BASE_SHIFT: i32 = 48;
+
+const ROOT_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Built-in modes as step intervals in semitones.
+const MODES: &[(&str, &[i32])] = &[
+    ("major", &[2, 2, 1, 2, 2, 2, 1]),
+    ("dorian", &[2, 1, 2, 2, 2, 1, 2]),
+    ("phrygian", &[1, 2, 2, 2, 1, 2, 2]),
+    ("lydian", &[2, 2, 2, 1, 2, 2, 1]),
+    ("mixolydian", &[2, 2, 1, 2, 2, 1, 2]),
+    ("minor", &[2, 1, 2, 2, 1, 2, 2]),
+    ("locrian", &[1, 2, 2, 1, 2, 2, 2]),
+    ("harmonic minor", &[2, 1, 2, 2, 1, 3, 1]),
+    ("melodic minor", &[2, 1, 2, 2, 2, 2, 1]),
+    ("major pentatonic", &[2, 2, 3, 2, 3]),
+    ("minor pentatonic", &[3, 2, 2, 3, 2]),
+    ("blues", &[3, 2, 1, 1, 3, 2]),
+    ("whole tone", &[2, 2, 2, 2, 2, 2]),
+    ("chromatic", &[1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]),
+];
+
+const MODE_ALIASES: &[(&str, &str)] = &[
+    ("maj", "major"),
+    ("ionian", "major"),
+    ("min", "minor"),
+    ("aeolian", "minor"),
+    ("natural minor", "minor"),
+    ("pentatonic", "major pentatonic"),
+];
+
+pub fn scale_modes() -> impl Iterator<Item = &'static str> {
+    MODES.iter().map(|(name, _)| *name)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Scale {
-    notes: [i32; 7],
+    notes: [i32; MAX_SCALE_NOTES],
+    len: usize,
+    period: i32,
     shift: i32,
 }
 
 impl Scale {
+    /// Builds a scale from the steps between consecutive degrees. The steps
+    /// sum to the period, so `[2, 2, 1, 2, 2, 2, 1]` is a major scale.
+    pub fn from_intervals(steps: &[i32]) -> Result<Self, String> {
+        if steps.is_empty() {
+            return Err("scale needs at least one interval".into());
+        }
+        if steps.len() > MAX_SCALE_NOTES {
+            return Err(format!("scale has more than {} notes", MAX_SCALE_NOTES));
+        }
+        if steps.iter().any(|&s| s <= 0) {
+            return Err("scale intervals must be positive".into());
+        }
+
+        let mut notes = [0; MAX_SCALE_NOTES];
+        let mut degree = 0;
+        for (note, step) in notes.iter_mut().zip(steps) {
+            *note = degree;
+            degree += step;
+        }
+        Ok(Self {
+            notes,
+            len: steps.len(),
+            period: degree,
+            shift: BASE_SHIFT,
+        })
+    }
+
+    pub fn mode(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let name = MODE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, canonical)| *canonical)
+            .unwrap_or(&name);
+        MODES
+            .iter()
+            .find(|(mode, _)| *mode == name)
+            .and_then(|(_, steps)| Self::from_intervals(steps).ok())
+    }
+
     pub fn shift(&mut self, i: i32) -> &mut Self {
         self.shift = i;
         self
     }
 
+    /// Sets the root pitch class (0 = C) in the default octave.
+    pub fn root(&mut self, pitch_class: i32) -> &mut Self {
+        self.shift = BASE_SHIFT + pitch_class.rem_euclid(12);
+        self
+    }
+
+    pub fn pitch_class(&self) -> i32 {
+        self.shift.rem_euclid(12)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn period(&self) -> i32 {
+        self.period
+    }
+
+    pub fn intervals(&self) -> impl Iterator<Item = i32> + '_ {
+        let degrees = &self.notes[..self.len];
+        degrees
+            .iter()
+            .zip(degrees.iter().skip(1).chain([&self.period]))
+            .map(|(a, b)| b - a)
+    }
+
+    pub fn mode_name(&self) -> Option<&'static str> {
+        MODES
+            .iter()
+            .find(|(_, steps)| self.intervals().eq(steps.iter().copied()))
+            .map(|(name, _)| *name)
+    }
+
     pub fn note(&self, index: i32) -> i32 {
-        if self.notes.is_empty() {
+        if self.is_empty() {
             return 0;
         }
 
-        let scale_len = self.notes.len() as i32;
-        let octave_offset = if index < 0 {
-            ((index + 1) / scale_len) - 1
-        } else {
-            index / scale_len
+        let scale_len = self.len as i32;
+        let octave_offset = index.div_euclid(scale_len);
+        let wrapped_index = index.rem_euclid(scale_len);
+        (self.notes[wrapped_index as usize] + self.shift) + octave_offset * self.period
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ROOT_NAMES[self.pitch_class() as usize])?;
+        match self.mode_name() {
+            Some(mode) => write!(f, " {}", mode),
+            None => self.intervals().try_for_each(|step| write!(f, " {}", step)),
+        }
+    }
+}
+
+/// Parses names like `"D dorian"`, `"Eb minor pentatonic"` or a root followed
+/// by custom step intervals, `"C 2 2 3 2 3"`. The root defaults to C.
+impl FromStr for Scale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<&str> = s.split_whitespace().collect();
+        let root = match words.first().and_then(|w| parse_root(w)) {
+            Some(root) => {
+                words.remove(0);
+                root
+            }
+            None => 0,
+        };
+        if words.is_empty() {
+            return Err(format!("missing mode in '{}'", s));
+        }
+
+        let steps: Result<Vec<i32>, _> = words.iter().map(|w| w.parse()).collect();
+        let mut scale = match steps {
+            Ok(steps) => Self::from_intervals(&steps)?,
+            Err(_) => Self::mode(&words.join(" "))
+                .ok_or_else(|| format!("unknown scale '{}'", words.join(" ")))?,
         };
-        let wrapped_index = ((index % scale_len) + scale_len) % scale_len;
-        (self.notes[wrapped_index as usize] + self.shift) + octave_offset * 12
+        scale.root(root);
+        Ok(scale)
     }
 }
 
-const BASE_SHIFT: i32 = 48;
-const MAJ: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
-const MIN: [i32; 7] = [0, 2, 3, 5, 7, 8, 10];
-const CHROM: [i32; 7] = [0, 1, 2, 3, 4, 5, 6];
+fn parse_root(word: &str) -> Option<i32> {
+    let mut chars = word.chars();
+    let base: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.as_str() {
+        "" => 0,
+        "#" => 1,
+        "b" => -1,
+        _ => return None,
+    };
+    Some((base + accidental).rem_euclid(12))
+}
+
+fn preset(mode: &str, pitch_class: i32) -> Scale {
+    let mut scale = Scale::mode(mode).expect("built-in mode");
+    scale.root(pitch_class);
+    scale
+}
 
 pub fn chromatic() -> Scale {
-    Scale {
-        notes: CHROM,
-        shift: BASE_SHIFT,
-    }
+    preset("chromatic", 0)
 }
 
 pub fn cmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT,
-    }
+    preset("major", 0)
 }
 pub fn cmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT,
-    }
+    preset("minor", 0)
 }
 pub fn csharpmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 1,
-    }
+    preset("major", 1)
 }
 pub fn csharpmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 1,
-    }
+    preset("minor", 1)
 }
 
 pub fn dmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 2,
-    }
+    preset("major", 2)
 }
 pub fn dmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 2,
-    }
+    preset("minor", 2)
 }
 pub fn dsharpmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 3,
-    }
+    preset("major", 3)
 }
 pub fn dsharpmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 3,
-    }
+    preset("minor", 3)
 }
 
 pub fn emaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 4,
-    }
+    preset("major", 4)
 }
 pub fn emin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 4,
-    }
+    preset("minor", 4)
 }
 
 pub fn fmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 5,
-    }
+    preset("major", 5)
 }
 pub fn fmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 5,
-    }
+    preset("minor", 5)
 }
 pub fn fsharpmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 6,
-    }
+    preset("major", 6)
 }
 pub fn fsharpmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 6,
-    }
+    preset("minor", 6)
 }
 
 pub fn gmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 7,
-    }
+    preset("major", 7)
 }
 pub fn gmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 7,
-    }
+    preset("minor", 7)
 }
 pub fn gsharpmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 8,
-    }
+    preset("major", 8)
 }
 pub fn gsharpmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 8,
-    }
+    preset("minor", 8)
 }
 
 pub fn amaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 9,
-    }
+    preset("major", 9)
 }
 pub fn amin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 9,
-    }
+    preset("minor", 9)
 }
 pub fn asharpmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 10,
-    }
+    preset("major", 10)
 }
 pub fn asharpmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 10,
-    }
+    preset("minor", 10)
 }
 
 pub fn bmaj() -> Scale {
-    Scale {
-        notes: MAJ,
-        shift: BASE_SHIFT + 11,
-    }
+    preset("major", 11)
 }
 pub fn bmin() -> Scale {
-    Scale {
-        notes: MIN,
-        shift: BASE_SHIFT + 11,
+    preset("minor", 11)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_named_scales() {
+        let dorian: Scale = "D dorian".parse().unwrap();
+        let degrees: Vec<i32> = (0..8).map(|i| dorian.note(i)).collect();
+        assert_eq!(degrees, vec![50, 52, 53, 55, 57, 59, 60, 62]);
+
+        assert_eq!(
+            "Eb harmonic minor".parse::<Scale>().unwrap().pitch_class(),
+            3
+        );
+        assert_eq!("C maj".parse::<Scale>().unwrap(), cmaj());
+        assert_eq!("a aeolian".parse::<Scale>().unwrap(), amin());
+        assert!("H lydian".parse::<Scale>().is_err());
+        assert!("C bebop".parse::<Scale>().is_err());
+    }
+
+    #[test]
+    fn test_variable_length_scales_wrap_octaves() {
+        let penta = Scale::mode("minor pentatonic").unwrap();
+        assert_eq!(penta.len(), 5);
+        assert_eq!(penta.note(5), 60);
+        assert_eq!(penta.note(-1), 46);
+
+        let chrom = chromatic();
+        assert_eq!(chrom.len(), 12);
+        assert_eq!(chrom.note(11), 59);
+        assert_eq!(chrom.note(12), 60);
+    }
+
+    #[test]
+    fn test_custom_intervals() {
+        let scale: Scale = "G 3 3 2 3 3 3 2".parse().unwrap();
+        assert_eq!(scale.period(), 19);
+        assert_eq!(scale.note(7), scale.note(0) + 19);
+        assert_eq!(scale.to_string(), "G 3 3 2 3 3 3 2");
+
+        assert!("C 2 0 3".parse::<Scale>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for mode in scale_modes() {
+            for root in 0..12 {
+                let mut scale = Scale::mode(mode).unwrap();
+                scale.root(root);
+                assert_eq!(scale.to_string().parse::<Scale>().unwrap(), scale);
+            }
+        }
     }
 }
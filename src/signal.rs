@@ -1,8 +1,11 @@
+use crate::{mix_bus::MixBus, utils::db_to_gain};
+
 pub struct Signal {
     pub current_sample: f32,
     pub sample_rate: usize,
     pub position: usize,
     pub global_volume: f32,
+    pub bus: MixBus,
 }
 
 impl Signal {
@@ -12,15 +15,21 @@ impl Signal {
             sample_rate,
             position: 0,
             global_volume: 1.0,
+            bus: MixBus::default(),
         }
     }
 
     pub fn add_sample(&mut self, sample: f32) {
-        let val = sample * self.global_volume;
-        self.current_sample += val;
-        if self.current_sample > 1. {
-            self.current_sample = 0.;
-        }
+        self.current_sample += sample;
+    }
+
+    pub fn add_sample_db(&mut self, sample: f32, gain_db: f32) {
+        self.current_sample += sample * db_to_gain(gain_db);
+    }
+
+    pub fn mix(&mut self) -> f32 {
+        self.bus
+            .process(self.current_sample, self.global_volume, self.sample_rate)
     }
 
     pub fn advance(&mut self) {
@@ -28,3 +37,42 @@ impl Signal {
         self.current_sample = 0.0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_keeps_headroom() {
+        let mut signal = Signal::new(44100);
+        signal.add_sample(0.8);
+        signal.add_sample(0.8);
+        signal.add_sample(-3.0);
+        assert!((signal.current_sample + 1.4).abs() < 1e-6);
+        assert!((signal.mix() + 1.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_source_gain_in_db() {
+        let mut signal = Signal::new(44100);
+        signal.add_sample_db(1.0, -6.0206);
+        assert!((signal.current_sample - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_global_volume_is_smoothed() {
+        let mut signal = Signal::new(44100);
+        signal.global_volume = 0.0;
+        signal.add_sample(1.0);
+        let first = signal.mix();
+        assert!(first > 0.9, "volume jumped to {}", first);
+
+        let mut last = first;
+        for _ in 0..4410 {
+            signal.advance();
+            signal.add_sample(1.0);
+            last = signal.mix();
+        }
+        assert!(last < 1e-3, "volume still at {}", last);
+    }
+}
@@ -1,114 +1,851 @@
 use nom::{
     Parser,
     branch::alt,
-    character::complete::{char, digit1},
-    combinator::opt,
-    multi::{many0, many1, separated_list1},
-    sequence::delimited,
+    bytes::complete::tag,
+    character::complete::{char, digit1, one_of, satisfy},
+    combinator::{cut, not, opt, peek, recognize, verify},
+    error::{ErrorKind, ParseError},
+    multi::{many0, many1},
+    sequence::{delimited, preceded, terminated},
 };
+use std::fmt;
+use std::ops::Range;
+
+type PResult<'a, T> = nom::IResult<&'a str, T, NotationError<'a>>;
+
+const DIVISION: &str = "a note, rest or group";
+
+/// Like `separated_list1`, but an element must follow every separator, so
+/// errors point inside the list instead of at the separator. `what` names
+/// the element in the error message.
+fn separated<'a, O>(
+    sep: char,
+    what: &'static str,
+    mut element: impl Parser<&'a str, Output = O, Error = NotationError<'a>>,
+) -> impl FnMut(&'a str) -> PResult<'a, Vec<O>> {
+    move |input| {
+        let (mut input, first) = element.parse(input)?;
+        let mut items = vec![first];
+        while let Some(rest) = input.strip_prefix(sep) {
+            match element.parse(rest) {
+                Ok((rest, item)) => {
+                    items.push(item);
+                    input = rest;
+                }
+                Err(nom::Err::Error(mut e)) => {
+                    if e.input.len() == rest.len() {
+                        e.expected = vec![what.to_string()];
+                    }
+                    return Err(nom::Err::Failure(e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((input, items))
+    }
+}
+
+/// Parser error that keeps the failure furthest into the input, along with
+/// the tokens that would have been accepted there.
+#[derive(Debug, PartialEq)]
+struct NotationError<'a> {
+    input: &'a str,
+    expected: Vec<String>,
+}
+
+impl<'a> ParseError<&'a str> for NotationError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        let expected = match kind {
+            ErrorKind::Digit => vec!["a number".to_string()],
+            ErrorKind::Verify => vec!["a number above 0".to_string()],
+            _ => Vec::new(),
+        };
+        Self { input, expected }
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self {
+            input,
+            expected: vec![format!("'{}'", c)],
+        }
+    }
 
+    fn or(mut self, other: Self) -> Self {
+        match self.input.len().cmp(&other.input.len()) {
+            std::cmp::Ordering::Less => self,
+            std::cmp::Ordering::Greater => other,
+            std::cmp::Ordering::Equal => {
+                for token in other.expected {
+                    if !self.expected.contains(&token) {
+                        self.expected.push(token);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+impl NotationError<'_> {
+    fn message(&self) -> String {
+        let found = match self.input.chars().next() {
+            Some(c) => format!("unexpected '{}'", c),
+            None => "unexpected end of track".to_string(),
+        };
+        match self.expected.as_slice() {
+            [] => found,
+            [only] => format!("{}, expected {}", found, only),
+            [init @ .., last] => format!("{}, expected {} or {}", found, init.join(", "), last),
+        }
+    }
+}
+
+/// A problem in the track notation. `line` and `column` are 1-based and
+/// `span` is the byte range of the offending text in the original input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackParseError {
+    pub line: usize,
+    pub column: usize,
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for TrackParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for TrackParseError {}
+
+/// A scale degree, moved by `chromatic_shift` semitones.
 #[derive(Clone, Debug, PartialEq)]
-struct ParsedNote {
-    degree: i32,
-    chromatic_shift: i32,
+pub struct ParsedNote {
+    pub degree: i32,
+    pub chromatic_shift: i32,
+    pub velocity: Option<u8>,
+    pub tie: bool,
+    pub slur: bool,
+}
+
+impl ParsedNote {
+    pub fn new(degree: i32) -> Self {
+        Self {
+            degree,
+            chromatic_shift: 0,
+            velocity: None,
+            tie: false,
+            slur: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChordRoot {
+    /// An absolute note name, in semitones above C.
+    Letter(i32),
+    /// A scale degree from 0 to 6; lowercase numerals are minor.
+    Roman {
+        degree: i32,
+        flat: bool,
+        minor: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chord {
+    pub root: ChordRoot,
+    /// One of the quality names in the notation, `""` for a plain triad.
+    pub quality: String,
+    pub voicing: Vec<Voicing>,
+    /// Slash bass of a letter chord, in semitones above C.
+    pub bass: Option<i32>,
+    pub velocity: Option<u8>,
+    pub tie: bool,
+    pub slur: bool,
+}
+
+impl Chord {
+    /// Semitones above the root, lowest first, with the bass below.
+    pub fn tones(&self) -> Vec<i32> {
+        let quality = match (self.root, self.quality.as_str()) {
+            (ChordRoot::Roman { minor: true, .. }, "") => "m",
+            (ChordRoot::Roman { minor: true, .. }, "7") => "m7",
+            (ChordRoot::Roman { minor: true, .. }, "6") => "m6",
+            (ChordRoot::Roman { minor: true, .. }, "9") => "m9",
+            (_, quality) => quality,
+        };
+        let mut tones = voice_chord(chord_intervals(quality), &self.voicing);
+        if let (ChordRoot::Letter(root), Some(bass)) = (self.root, self.bass) {
+            tones.insert(0, (bass - root).rem_euclid(12) - 12);
+        }
+        tones
+    }
+}
+
+/// Register and transposition applied to everything inside an item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Shift {
+    pub octaves: i32,
+    pub degrees: i32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
-enum Item {
+pub enum Item {
     Note(ParsedNote),
+    Chord(Chord),
     Rest,
     Sequence(Vec<Division>),
     Polyphony(Vec<Vec<Division>>),
+    Shift(Box<Item>, Shift),
+    /// Plays with the given percent chance, rolled again on every loop.
+    Chance(Box<Item>, u8),
+    /// Plays one of the options, chosen again on every loop.
+    Choice(Vec<Vec<Division>>),
+}
+
+fn shifted(item: Item, shift: Shift) -> Item {
+    if shift == Shift::default() {
+        item
+    } else {
+        Item::Shift(Box::new(item), shift)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Division {
-    item: Item,
-    weight: usize,
+pub struct Division {
+    pub item: Item,
+    pub weight: usize,
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Bar {
-    divisions: Vec<Division>,
+pub struct Bar {
+    pub divisions: Vec<Division>,
+    /// The bar's own time signature, if it has one; otherwise the track's.
+    pub meter: Option<TimeSignature>,
+}
+
+const MAX_METER_BEATS: u32 = 64;
+
+/// Beats per bar over the note value of a beat: `7/8` is seven eighths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeSignature {
+    beats: u32,
+    unit: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self { beats: 4, unit: 4 }
+    }
+}
+
+impl TimeSignature {
+    /// `None` unless `beats` is 1 to 64 and `unit` is 1, 2, 4, 8 or 16, the
+    /// signatures the notation accepts.
+    pub fn new(beats: u32, unit: u32) -> Option<Self> {
+        match (beats, unit) {
+            (1..=MAX_METER_BEATS, 1 | 2 | 4 | 8 | 16) => Some(Self { beats, unit }),
+            _ => None,
+        }
+    }
+
+    pub fn beats(&self) -> u32 {
+        self.beats
+    }
+
+    pub fn unit(&self) -> u32 {
+        self.unit
+    }
+
+    /// Length of a bar in quarter notes, the beats counted by the tempo.
+    pub fn quarters(&self) -> f32 {
+        self.beats as f32 * 4.0 / self.unit as f32
+    }
+
+    /// Length of a bar in 16ths.
+    pub fn sixteenths(&self) -> usize {
+        (self.beats * 16 / self.unit) as usize
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.unit)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    pub bars: Vec<Bar>,
+}
+
+/// How layers of different lengths line up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayerMode {
+    /// Every layer is stretched over the length of the longest one.
+    #[default]
+    Stretch,
+    /// Bars keep their length and every layer loops on its own. The track
+    /// repeats after `length` bars, or after the least common multiple of
+    /// the layer lengths.
+    Polymeter { length: Option<usize> },
+}
+
+/// A parsed track, with repeats and sections expanded. Parse one with
+/// `str::parse` and print it back as notation with `Display`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedTrackAST {
+    pub layers: Vec<Layer>,
+    pub layer_mode: LayerMode,
+    /// Time signature of the bars that don't set their own.
+    pub meter: TimeSignature,
+    pub lanes: Vec<ParsedLane>,
+}
+
+impl ParsedTrackAST {
+    /// Number of bars before the track repeats. Polymeter tracks are cut
+    /// off at `MAX_POLYMETER_BARS`.
+    pub fn bar_count(&self) -> usize {
+        match self.layer_mode {
+            LayerMode::Stretch => self.layers.iter().map(|l| l.bars.len()).max().unwrap_or(0),
+            LayerMode::Polymeter { .. } => self
+                .polymeter_length()
+                .map_or(MAX_POLYMETER_BARS, |length| length.min(MAX_POLYMETER_BARS)),
+        }
+    }
+
+    /// Length of a polymeter track as written, or `None` if the layers'
+    /// common multiple overflows.
+    fn polymeter_length(&self) -> Option<usize> {
+        let mut lengths = self.layers.iter().map(|l| l.bars.len()).filter(|&n| n > 0);
+        match self.layer_mode {
+            LayerMode::Polymeter {
+                length: Some(length),
+            } => Some(length),
+            _ => lengths.try_fold(1usize, |a, b| (a / crate::utils::gcd(a, b)).checked_mul(b)),
+        }
+    }
+
+    /// Time signature of every bar of the track. Stretched layers follow
+    /// the bar lines of the longest one; polymeter tracks have a single
+    /// meter.
+    pub fn meters(&self) -> Vec<TimeSignature> {
+        let meter = |bar: &Bar| bar.meter.unwrap_or(self.meter);
+        match self.layer_mode {
+            LayerMode::Stretch => self
+                .layers
+                .iter()
+                .rev()
+                .max_by_key(|l| l.bars.len())
+                .map(|l| l.bars.iter().map(meter).collect())
+                .unwrap_or_default(),
+            LayerMode::Polymeter { .. } => {
+                let first = self.layers.iter().flat_map(|l| &l.bars).next();
+                vec![first.map_or(self.meter, meter); self.bar_count()]
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ParsedTrackAST {
+    type Err = Vec<TrackParseError>;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        parse_notation(notation)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Step,
+    Ramp,
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Layer {
-    bars: Vec<Bar>,
+pub enum LaneItem {
+    Value(f32),
+    /// Keeps the previous value going.
+    Hold,
+    Sequence(Vec<LaneDivision>),
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct ParsedTrackAST {
-    layers: Vec<Layer>,
+pub struct LaneDivision {
+    pub item: LaneItem,
+    pub weight: usize,
 }
 
-fn parse_number(input: &str) -> nom::IResult<&str, i32> {
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedLane {
+    pub name: String,
+    pub interpolation: Interpolation,
+    pub bars: Vec<Vec<LaneDivision>>,
+}
+
+fn parse_number(input: &str) -> PResult<'_, i32> {
     let (input, sign) = opt(char('-')).parse(input)?;
     let (input, num) = digit1.parse(input)?;
     let val: i32 = num.parse().unwrap();
     Ok((input, if sign.is_some() { -val } else { val }))
 }
 
-fn parse_note(input: &str) -> nom::IResult<&str, Item> {
+fn parse_note(input: &str) -> PResult<'_, Item> {
     let (input, degree) = parse_number(input)?;
-    let (input, chromatic_shift) =
-        opt(alt((char('+').map(|_| 1i32), char('-').map(|_| -1i32)))).parse(input)?;
+    let (input, shifts) = many0(one_of("+-")).parse(input)?;
+    let chromatic_shift = shifts.iter().map(|&c| if c == '+' { 1 } else { -1 }).sum();
     Ok((
         input,
         Item::Note(ParsedNote {
-            degree,
-            chromatic_shift: chromatic_shift.unwrap_or(0),
+            chromatic_shift,
+            ..ParsedNote::new(degree)
         }),
     ))
 }
 
-fn parse_rest(input: &str) -> nom::IResult<&str, Item> {
+fn parse_rest(input: &str) -> PResult<'_, Item> {
     let (input, _) = char('_').parse(input)?;
     Ok((input, Item::Rest))
 }
 
-fn parse_nested_bar(input: &str) -> nom::IResult<&str, Item> {
+fn parse_transposition(input: &str) -> PResult<'_, Shift> {
+    let (input, sign) = alt((char('+').map(|_| 1), char('-').map(|_| -1))).parse(input)?;
+    let (input, num) = digit1.parse(input)?;
+    let degrees = sign * num.parse::<i32>().unwrap_or(0);
+    Ok((
+        input,
+        Shift {
+            octaves: 0,
+            degrees,
+        },
+    ))
+}
+
+fn parse_nested_bar(input: &str) -> PResult<'_, Item> {
     let (input, divisions) = delimited(char('('), parse_divisions, char(')')).parse(input)?;
-    Ok((input, Item::Sequence(divisions)))
+    let (input, shift) = opt(parse_transposition).parse(input)?;
+    Ok((
+        input,
+        shifted(Item::Sequence(divisions), shift.unwrap_or_default()),
+    ))
 }
 
-fn parse_polyphonic_item(input: &str) -> nom::IResult<&str, Item> {
+fn parse_polyphonic_item(input: &str) -> PResult<'_, Item> {
     let (input, layers) = delimited(
         char('{'),
-        separated_list1(char('&'), parse_divisions),
+        separated('&', DIVISION, parse_divisions),
         char('}'),
     )
     .parse(input)?;
-    Ok((input, Item::Polyphony(layers)))
+    let (input, shift) = opt(parse_transposition).parse(input)?;
+    Ok((
+        input,
+        shifted(Item::Polyphony(layers), shift.unwrap_or_default()),
+    ))
+}
+
+// Longer names come before their prefixes so the first match wins.
+const CHORD_QUALITIES: &[(&str, &[i32])] = &[
+    ("maj9", &[0, 4, 7, 11, 14]),
+    ("maj7", &[0, 4, 7, 11]),
+    ("m7b5", &[0, 3, 6, 10]),
+    ("7sus4", &[0, 5, 7, 10]),
+    ("add9", &[0, 4, 7, 14]),
+    ("dim7", &[0, 3, 6, 9]),
+    ("sus2", &[0, 2, 7]),
+    ("sus4", &[0, 5, 7]),
+    ("maj", &[0, 4, 7]),
+    ("min", &[0, 3, 7]),
+    ("dim", &[0, 3, 6]),
+    ("aug", &[0, 4, 8]),
+    ("M7", &[0, 4, 7, 11]),
+    ("m9", &[0, 3, 7, 10, 14]),
+    ("m7", &[0, 3, 7, 10]),
+    ("m6", &[0, 3, 7, 9]),
+    ("o7", &[0, 3, 6, 9]),
+    ("M", &[0, 4, 7]),
+    ("m", &[0, 3, 7]),
+    ("o", &[0, 3, 6]),
+    ("9", &[0, 4, 7, 10, 14]),
+    ("7", &[0, 4, 7, 10]),
+    ("6", &[0, 4, 7, 9]),
+    ("", &[0, 4, 7]),
+];
+
+const ROMAN_NUMERALS: &[(&str, i32)] = &[
+    ("VII", 6),
+    ("VI", 5),
+    ("IV", 3),
+    ("V", 4),
+    ("III", 2),
+    ("II", 1),
+    ("I", 0),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Voicing {
+    /// Moves the lowest tone up an octave this many times.
+    Inversion(usize),
+    /// Raises every other tone an octave.
+    Spread,
+}
+
+fn parse_chord_quality(input: &str) -> PResult<'_, &'static str> {
+    let (name, _) = CHORD_QUALITIES
+        .iter()
+        .find(|(name, _)| input.starts_with(name))
+        .expect("empty quality always matches");
+    Ok((&input[name.len()..], name))
 }
 
-fn parse_item(input: &str) -> nom::IResult<&str, Item> {
+fn chord_intervals(quality: &str) -> &'static [i32] {
+    CHORD_QUALITIES
+        .iter()
+        .find(|(name, _)| *name == quality)
+        .map(|(_, intervals)| *intervals)
+        .unwrap_or(&[0, 4, 7])
+}
+
+fn parse_letter_root(input: &str) -> PResult<'_, i32> {
+    let (input, letter) = one_of("CDEFGAB").parse(input)?;
+    let (input, accidental) =
+        opt(alt((char('#').map(|_| 1i32), char('b').map(|_| -1i32)))).parse(input)?;
+    let base = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        _ => 11,
+    };
+    Ok((input, (base + accidental.unwrap_or(0)).rem_euclid(12)))
+}
+
+fn parse_roman_root(input: &str) -> PResult<'_, (i32, bool)> {
+    for (numeral, degree) in ROMAN_NUMERALS {
+        if let Some(rest) = input.strip_prefix(numeral) {
+            return Ok((rest, (*degree, false)));
+        }
+        if let Some(rest) = input.strip_prefix(numeral.to_lowercase().as_str()) {
+            return Ok((rest, (*degree, true)));
+        }
+    }
+    Err(nom::Err::Error(NotationError::from_error_kind(
+        input,
+        ErrorKind::Tag,
+    )))
+}
+
+fn parse_voicing(input: &str) -> PResult<'_, Vec<Voicing>> {
+    many0(alt((
+        preceded(char('@'), digit1).map(|n: &str| Voicing::Inversion(n.parse().unwrap_or(0))),
+        char('%').map(|_| Voicing::Spread),
+    )))
+    .parse(input)
+}
+
+/// Stacks the chord intervals, applies inversions (lowest tone up an octave)
+/// and spread (every other tone up an octave), returning sorted semitones.
+fn voice_chord(intervals: &[i32], voicing: &[Voicing]) -> Vec<i32> {
+    let mut tones = intervals.to_vec();
+    for v in voicing {
+        match *v {
+            Voicing::Inversion(n) => {
+                for _ in 0..n {
+                    let low = tones.remove(0);
+                    tones.push(low + 12);
+                }
+            }
+            Voicing::Spread => {
+                for t in tones.iter_mut().skip(1).step_by(2) {
+                    *t += 12;
+                }
+                tones.sort();
+            }
+        }
+    }
+    tones
+}
+
+fn parse_letter_chord(input: &str) -> PResult<'_, Item> {
+    let (input, root) = parse_letter_root(input)?;
+    let (input, quality) = parse_chord_quality(input)?;
+    let (input, voicing) = parse_voicing(input)?;
+    let (input, bass) = opt(preceded(
+        char('/'),
+        terminated(
+            parse_letter_root,
+            not(peek(satisfy(|c| c.is_ascii_alphanumeric()))),
+        ),
+    ))
+    .parse(input)?;
+    Ok((
+        input,
+        Item::Chord(Chord {
+            root: ChordRoot::Letter(root),
+            quality: quality.to_string(),
+            voicing,
+            bass,
+            velocity: None,
+            tie: false,
+            slur: false,
+        }),
+    ))
+}
+
+fn parse_roman_chord(input: &str) -> PResult<'_, Item> {
+    let (input, flat) = opt(char('b')).parse(input)?;
+    let (input, (degree, minor)) = parse_roman_root(input)?;
+    let (input, quality) = parse_chord_quality(input)?;
+    let (input, voicing) = parse_voicing(input)?;
+    Ok((
+        input,
+        Item::Chord(Chord {
+            root: ChordRoot::Roman {
+                degree,
+                flat: flat.is_some(),
+                minor,
+            },
+            quality: quality.to_string(),
+            voicing,
+            bass: None,
+            velocity: None,
+            tie: false,
+            slur: false,
+        }),
+    ))
+}
+
+fn parse_choice(input: &str) -> PResult<'_, Item> {
+    let (input, options) = delimited(
+        char('['),
+        separated('|', DIVISION, parse_divisions),
+        char(']'),
+    )
+    .parse(input)?;
+    Ok((input, Item::Choice(options)))
+}
+
+/// Spreads `hits` onsets as evenly as possible over `steps` (Bjorklund's
+/// algorithm), then rotates the pattern left by `rotation` steps.
+fn euclidean_pattern(hits: usize, steps: usize, rotation: usize) -> Vec<bool> {
+    let hits = hits.min(steps);
+    let pattern = if hits == 0 || hits == steps {
+        vec![hits > 0; steps]
+    } else {
+        let mut a = vec![vec![true]; hits];
+        let mut b = vec![vec![false]; steps - hits];
+        while b.len() > 1 {
+            let m = a.len().min(b.len());
+            let rest = if a.len() > m {
+                a.split_off(m)
+            } else {
+                b.split_off(m)
+            };
+            for (head, tail) in a.iter_mut().zip(b) {
+                head.extend(tail);
+            }
+            b = rest;
+        }
+        a.into_iter().chain(b).flatten().collect()
+    };
+    let rotation = rotation % steps;
+    (0..steps)
+        .map(|i| pattern[(i + rotation) % steps])
+        .collect()
+}
+
+/// Expands `item` into a sequence playing it on the euclidean hits, with
+/// the gaps between hits merged into weighted rests.
+fn euclidean_item(item: Item, hits: usize, steps: usize, rotation: usize) -> Item {
+    let mut divisions: Vec<Division> = Vec::new();
+    for hit in euclidean_pattern(hits, steps, rotation) {
+        match divisions.last_mut() {
+            Some(last) if !hit && last.item == Item::Rest => last.weight += 1,
+            _ => divisions.push(Division {
+                item: if hit { item.clone() } else { Item::Rest },
+                weight: 1,
+            }),
+        }
+    }
+    Item::Sequence(divisions)
+}
+
+const MAX_EUCLIDEAN_STEPS: usize = 64;
+
+fn parse_euclidean_steps(input: &str) -> PResult<'_, usize> {
+    let (rest, steps) = digit1(input)?;
+    match steps.parse() {
+        Ok(steps @ 1..=MAX_EUCLIDEAN_STEPS) => Ok((rest, steps)),
+        _ => Err(nom::Err::Failure(NotationError {
+            input,
+            expected: vec![format!("a number of steps from 1 to {MAX_EUCLIDEAN_STEPS}")],
+        })),
+    }
+}
+
+fn parse_euclidean_args(input: &str) -> PResult<'_, (usize, usize, usize)> {
+    let number = || digit1.map(|n: &str| n.parse::<usize>().unwrap_or(0));
+    let (input, (hits, steps, rotation)) = preceded(
+        char('e'),
+        delimited(
+            char('('),
+            (
+                number(),
+                preceded(char(','), parse_euclidean_steps),
+                opt(preceded(char(','), number())),
+            ),
+            char(')'),
+        ),
+    )
+    .parse(input)?;
+    Ok((input, (hits, steps, rotation.unwrap_or(0))))
+}
+
+fn parse_euclidean(input: &str) -> PResult<'_, Item> {
+    let (input, (hits, steps, rotation)) = parse_euclidean_args(input)?;
+    let note = Item::Note(ParsedNote::new(0));
+    Ok((input, euclidean_item(note, hits, steps, rotation)))
+}
+
+fn parse_item(input: &str) -> PResult<'_, Item> {
     alt((
         parse_polyphonic_item,
+        parse_choice,
+        parse_euclidean,
         parse_nested_bar,
         parse_note,
         parse_rest,
+        parse_letter_chord,
+        parse_roman_chord,
     ))
     .parse(input)
 }
 
-fn parse_division(input: &str) -> nom::IResult<&str, (Item, usize)> {
-    let (input, item) = parse_item(input)?;
-    let (input, asterisks) = many0(char('*')).parse(input)?;
-    let weight = 1 + asterisks.len();
-    Ok((input, (item, weight)))
+pub const DEFAULT_VELOCITY: u8 = 100;
+pub(crate) const ACCENT_VELOCITY: u8 = 127;
+pub(crate) const GHOST_VELOCITY: u8 = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum NoteModifier {
+    Velocity(u8),
+    Tie,
+    Slur,
+    Chance(u8),
 }
 
-fn parse_division_with_separator(input: &str) -> nom::IResult<&str, Division> {
-    let (input, (item, weight)) = parse_division(input)?;
-    Ok((input, Division { item, weight }))
+fn parse_modifier(input: &str) -> PResult<'_, NoteModifier> {
+    alt((
+        char('!').map(|_| NoteModifier::Velocity(ACCENT_VELOCITY)),
+        char('.').map(|_| NoteModifier::Velocity(GHOST_VELOCITY)),
+        preceded(char('^'), digit1)
+            .map(|n: &str| NoteModifier::Velocity(n.parse::<u32>().unwrap_or(127).min(127) as u8)),
+        char('~').map(|_| NoteModifier::Tie),
+        char('=').map(|_| NoteModifier::Slur),
+        preceded(char('?'), opt(digit1)).map(|n: Option<&str>| {
+            let percent = n.map_or(50, |n| n.parse::<u32>().unwrap_or(100).min(100));
+            NoteModifier::Chance(percent as u8)
+        }),
+    ))
+    .parse(input)
 }
 
-fn parse_divisions(input: &str) -> nom::IResult<&str, Vec<Division>> {
-    separated_list1(char('/'), |i| parse_division_with_separator(i)).parse(input)
+/// Applies a modifier to every note inside `item`. Velocities only fill in
+/// notes that have none of their own.
+fn apply_modifier(item: &mut Item, modifier: NoteModifier) {
+    match item {
+        Item::Note(note) => match modifier {
+            NoteModifier::Velocity(velocity) => {
+                note.velocity.get_or_insert(velocity);
+            }
+            NoteModifier::Tie => note.tie = true,
+            NoteModifier::Slur => note.slur = true,
+            NoteModifier::Chance(_) => {}
+        },
+        Item::Chord(chord) => match modifier {
+            NoteModifier::Velocity(velocity) => {
+                chord.velocity.get_or_insert(velocity);
+            }
+            NoteModifier::Tie => chord.tie = true,
+            NoteModifier::Slur => chord.slur = true,
+            NoteModifier::Chance(_) => {}
+        },
+        Item::Rest => {}
+        Item::Sequence(divs) => {
+            for div in divs {
+                apply_modifier(&mut div.item, modifier);
+            }
+        }
+        Item::Polyphony(layers) => {
+            for div in layers.iter_mut().flatten() {
+                apply_modifier(&mut div.item, modifier);
+            }
+        }
+        Item::Shift(inner, _) | Item::Chance(inner, _) => apply_modifier(inner, modifier),
+        Item::Choice(options) => {
+            for div in options.iter_mut().flatten() {
+                apply_modifier(&mut div.item, modifier);
+            }
+        }
+    }
 }
 
-fn parse_one_polyphony_layer(input: &str) -> nom::IResult<&str, Vec<Division>> {
+/// Returns the item, its weight and the persistent register change from
+/// `>`/`<` markers. `'`/`,` only shift this one item.
+fn parse_division(input: &str) -> PResult<'_, (Item, usize, i32)> {
+    let (input, marks) = many0(one_of("',><")).parse(input)?;
+    let count = |c| marks.iter().filter(|&&m| m == c).count() as i32;
+    let (input, mut item) = parse_item(input)?;
+    let (input, euclidean) = opt(parse_euclidean_args).parse(input)?;
+    if let Some((hits, steps, rotation)) = euclidean {
+        item = euclidean_item(item, hits, steps, rotation);
+    }
+    let (input, modifiers) = many0(parse_modifier).parse(input)?;
+    for modifier in modifiers {
+        match modifier {
+            NoteModifier::Chance(percent) => item = Item::Chance(Box::new(item), percent),
+            _ => apply_modifier(&mut item, modifier),
+        }
+    }
+    let (input, asterisks) = many0(char('*')).parse(input)?;
+    let weight = 1 + asterisks.len();
+    let local = Shift {
+        octaves: count('\'') - count(','),
+        degrees: 0,
+    };
+    Ok((
+        input,
+        (shifted(item, local), weight, count('>') - count('<')),
+    ))
+}
+
+fn parse_divisions(input: &str) -> PResult<'_, Vec<Division>> {
+    let (input, parsed) = separated('/', DIVISION, parse_division)(input)?;
+    let mut register = 0;
+    let divisions = parsed
+        .into_iter()
+        .map(|(item, weight, change)| {
+            register += change;
+            let shift = Shift {
+                octaves: register,
+                degrees: 0,
+            };
+            Division {
+                item: shifted(item, shift),
+                weight,
+            }
+        })
+        .collect();
+    Ok((input, divisions))
+}
+
+fn parse_one_polyphony_layer(input: &str) -> PResult<'_, Vec<Division>> {
     alt((
         delimited(char('('), |i| parse_divisions(i), char(')')),
         |i| parse_divisions(i),
@@ -116,9 +853,8 @@ fn parse_one_polyphony_layer(input: &str) -> nom::IResult<&str, Vec<Division>> {
     .parse(input)
 }
 
-fn parse_polyphonic_divisions(input: &str) -> nom::IResult<&str, Vec<Division>> {
-    let (input, poly_layers) =
-        separated_list1(char('&'), parse_one_polyphony_layer).parse(input)?;
+fn parse_polyphonic_divisions(input: &str) -> PResult<'_, Vec<Division>> {
+    let (input, poly_layers) = separated('&', DIVISION, parse_one_polyphony_layer)(input)?;
 
     if poly_layers.len() == 1 {
         return Ok((input, poly_layers.into_iter().next().unwrap()));
@@ -134,59 +870,556 @@ fn parse_polyphonic_divisions(input: &str) -> nom::IResult<&str, Vec<Division>>
     Ok((input, result))
 }
 
-fn parse_bar(input: &str) -> nom::IResult<&str, Bar> {
+/// `7/8`: beats per bar over the note value of a beat, one of 1, 2, 4, 8
+/// or 16.
+fn parse_time_signature(input: &str) -> PResult<'_, TimeSignature> {
+    let (rest, beats) = terminated(digit1, char('/')).parse(input)?;
+    let fail = |input, expected: &str| {
+        Err(nom::Err::Failure(NotationError {
+            input,
+            expected: vec![expected.to_string()],
+        }))
+    };
+    let beats = match beats.parse() {
+        Ok(beats @ 1..=MAX_METER_BEATS) => beats,
+        _ => return fail(input, "a number of beats from 1 to 64"),
+    };
+    let (input, unit) = cut(digit1).parse(rest)?;
+    match unit
+        .parse()
+        .ok()
+        .and_then(|unit| TimeSignature::new(beats, unit))
+    {
+        Some(meter) => Ok((input, meter)),
+        None => fail(rest, "a note value of 1, 2, 4, 8 or 16"),
+    }
+}
+
+fn parse_bar(input: &str) -> PResult<'_, Bar> {
+    let (input, meter) = opt(parse_time_signature).parse(input)?;
     let (input, divisions) = alt((
         delimited(char('('), |i| parse_divisions(i), char(')')),
         delimited(char('{'), |i| parse_polyphonic_divisions(i), char('}')),
     ))
     .parse(input)?;
-    Ok((input, Bar { divisions }))
+    let (input, shift) = opt(parse_transposition).parse(input)?;
+    let divisions = match shift {
+        Some(shift) => vec![Division {
+            item: shifted(Item::Sequence(divisions), shift),
+            weight: 1,
+        }],
+        None => divisions,
+    };
+    Ok((input, Bar { divisions, meter }))
 }
 
-fn parse_layer_sequence(input: &str) -> nom::IResult<&str, Layer> {
-    let (input, bars) = many1(|i| parse_bar(i)).parse(input)?;
-    Ok((input, Layer { bars }))
+#[derive(Clone, Debug, PartialEq)]
+enum Statement {
+    Define(String, Vec<Bar>),
+    Play(Vec<Bar>),
+    Reference(String),
+    Lane(ParsedLane),
+    // `&` starts another layer.
+    Layer,
+    Polymeter(Option<usize>),
+    Meter(TimeSignature),
 }
 
-fn parse_track_section(input: &str) -> nom::IResult<&str, Vec<Layer>> {
-    let (input, layer) = parse_layer_sequence(input)?;
-    Ok((input, vec![layer]))
+fn parse_lane_value(input: &str) -> PResult<'_, f32> {
+    let (input, text) =
+        recognize((opt(char('-')), digit1, opt((char('.'), digit1)))).parse(input)?;
+    Ok((input, text.parse().unwrap_or(0.0)))
 }
 
-fn parse_track(input: &str) -> nom::IResult<&str, ParsedTrackAST> {
-    let (input, sections) = many1(|i| parse_track_section(i)).parse(input)?;
-    let layers = sections.into_iter().flatten().collect();
-    Ok((input, ParsedTrackAST { layers }))
+fn parse_lane_division(input: &str) -> PResult<'_, LaneDivision> {
+    let (input, item) = alt((
+        parse_lane_value.map(LaneItem::Value),
+        char('_').map(|_| LaneItem::Hold),
+        delimited(char('('), parse_lane_divisions, char(')')).map(LaneItem::Sequence),
+    ))
+    .parse(input)?;
+    let (input, asterisks) = many0(char('*')).parse(input)?;
+    Ok((
+        input,
+        LaneDivision {
+            item,
+            weight: 1 + asterisks.len(),
+        },
+    ))
+}
+
+fn parse_lane_divisions(input: &str) -> PResult<'_, Vec<LaneDivision>> {
+    separated('/', "a value, rest or group", parse_lane_division)(input)
+}
+
+fn parse_lane_bars(input: &str) -> PResult<'_, Vec<Vec<LaneDivision>>> {
+    let repeated_bar = (
+        delimited(char('('), parse_lane_divisions, char(')')),
+        parse_repeat_count,
+    )
+        .map(|(bar, count)| vec![bar; count]);
+    let (input, bars) = many1(repeated_bar).parse(input)?;
+    Ok((input, bars.into_iter().flatten().collect()))
+}
+
+/// `cutoff: (0.1/0.5/0.9)` steps between values, `cutoff: ramp (0/1)`
+/// glides from each value to the next.
+fn parse_lane(input: &str) -> PResult<'_, ParsedLane> {
+    let (rest, _) = satisfy(|c| c.is_ascii_lowercase()).parse(input)?;
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = input[..len + 1].to_string();
+    let (input, _) = char(':').parse(&rest[len..])?;
+    let (input, interpolation) = opt(alt((
+        tag("step").map(|_| Interpolation::Step),
+        tag("ramp").map(|_| Interpolation::Ramp),
+    )))
+    .parse(input)?;
+    let (input, bars) = parse_lane_bars(input)?;
+    Ok((
+        input,
+        ParsedLane {
+            name,
+            interpolation: interpolation.unwrap_or_default(),
+            bars,
+        },
+    ))
+}
+
+const MAX_REPEATS: usize = 1024;
+
+/// `x4` after a bar plays it four times.
+fn parse_repeat_count(input: &str) -> PResult<'_, usize> {
+    let (rest, count) = opt(preceded(char('x'), digit1)).parse(input)?;
+    match count.map(str::parse) {
+        None => Ok((rest, 1)),
+        Some(Ok(count @ 1..=MAX_REPEATS)) => Ok((rest, count)),
+        Some(_) => Err(nom::Err::Failure(NotationError {
+            input: &input[1..],
+            expected: vec![format!("a repeat count from 1 to {MAX_REPEATS}")],
+        })),
+    }
+}
+
+fn parse_repeated_bar(input: &str) -> PResult<'_, Vec<Bar>> {
+    let (input, bar) = parse_bar(input)?;
+    let (input, count) = parse_repeat_count(input)?;
+    Ok((input, vec![bar; count]))
+}
+
+fn parse_bars(input: &str) -> PResult<'_, Vec<Bar>> {
+    // A time signature after `x<n>` keeps the space in front of it.
+    let (input, bars) = many1(preceded(opt(char(' ')), parse_repeated_bar)).parse(input)?;
+    Ok((input, bars.into_iter().flatten().collect()))
+}
+
+fn parse_section_name(input: &str) -> PResult<'_, String> {
+    let (rest, _) = satisfy(|c| c.is_ascii_uppercase()).parse(input)?;
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    Ok((&rest[len..], input[..len + 1].to_string()))
+}
+
+/// `polymeter` loops layers at their own length; `polymeter 12` also sets
+/// the length of the track.
+fn parse_polymeter(input: &str) -> PResult<'_, Option<usize>> {
+    let (input, _) = terminated(
+        tag("polymeter"),
+        not(peek(satisfy(|c| {
+            c.is_ascii_alphanumeric() || c == '_' || c == ':'
+        }))),
+    )
+    .parse(input)?;
+    opt(preceded(
+        (char(' '), peek(terminated(digit1, not(char('/'))))),
+        cut(verify(digit1.map(|n: &str| n.parse().unwrap_or(0)), |&n| {
+            n > 0
+        })),
+    ))
+    .parse(input)
+}
+
+/// `meter 3/4` sets the time signature of every bar without its own.
+fn parse_meter(input: &str) -> PResult<'_, TimeSignature> {
+    let (input, _) = terminated(
+        tag("meter"),
+        not(peek(satisfy(|c| {
+            c.is_ascii_alphanumeric() || c == '_' || c == ':'
+        }))),
+    )
+    .parse(input)?;
+    cut(preceded(char(' '), parse_time_signature)).parse(input)
 }
 
+fn parse_statement(input: &str) -> PResult<'_, Statement> {
+    alt((
+        parse_polymeter.map(Statement::Polymeter),
+        parse_meter.map(Statement::Meter),
+        char('&').map(|_| Statement::Layer),
+        parse_lane.map(Statement::Lane),
+        (parse_section_name, char('='), parse_bars)
+            .map(|(name, _, bars)| Statement::Define(name, bars)),
+        parse_bars.map(Statement::Play),
+        parse_section_name.map(Statement::Reference),
+    ))
+    .parse(input)
+}
+
+pub(crate) const MAX_POLYMETER_BARS: usize = 4096;
+
+/// Replaces section references with their bars. Definitions may come after
+/// the song line that uses them. Errors carry the span of the statement.
+fn expand_sections(
+    statements: Vec<(Range<usize>, Statement)>,
+) -> Result<ParsedTrackAST, (Range<usize>, String)> {
+    let mut sections = std::collections::HashMap::new();
+    for (_, statement) in &statements {
+        if let Statement::Define(name, bars) = statement {
+            sections.insert(name.clone(), bars.clone());
+        }
+    }
+
+    // Each layer with the span of the `&` that started it.
+    let mut layers = vec![(0..0, Vec::new())];
+    let mut layer_mode = LayerMode::Stretch;
+    let mut polymeter_span = None;
+    let mut meter = TimeSignature::default();
+    let mut meter_span = None;
+    let mut lanes: Vec<(Range<usize>, ParsedLane)> = Vec::new();
+    for (span, statement) in statements {
+        let bars = &mut layers.last_mut().unwrap().1;
+        match statement {
+            Statement::Define(..) => {}
+            Statement::Lane(lane) => {
+                if lanes.iter().any(|(_, l)| l.name == lane.name) {
+                    return Err((span, format!("lane '{}' is defined twice", lane.name)));
+                }
+                lanes.push((span, lane));
+            }
+            Statement::Play(b) => bars.extend(b),
+            Statement::Reference(name) => {
+                let section = sections
+                    .get(&name)
+                    .ok_or_else(|| (span, format!("unknown section '{}'", name)))?;
+                bars.extend(section.iter().cloned());
+            }
+            Statement::Layer => layers.push((span, Vec::new())),
+            Statement::Polymeter(length) => {
+                if polymeter_span.replace(span.clone()).is_some() {
+                    return Err((span, "polymeter is set twice".into()));
+                }
+                layer_mode = LayerMode::Polymeter { length };
+            }
+            Statement::Meter(m) => {
+                if meter_span.replace(span.clone()).is_some() {
+                    return Err((span, "meter is set twice".into()));
+                }
+                meter = m;
+            }
+        }
+    }
+    // Every layer may start with `&`, leaving the first one empty.
+    if layers.len() > 1 && layers[0].1.is_empty() {
+        layers.remove(0);
+    }
+    if let Some((span, _)) = layers.iter().find(|(_, bars)| bars.is_empty()) {
+        return Err(if span.is_empty() {
+            (0..0, "track has no bars to play".into())
+        } else {
+            (span.clone(), "layer has no bars".into())
+        });
+    }
+    let ast = ParsedTrackAST {
+        layers: layers.into_iter().map(|(_, bars)| Layer { bars }).collect(),
+        layer_mode,
+        meter,
+        lanes: lanes.iter().map(|(_, lane)| lane.clone()).collect(),
+    };
+    if let Some(span) = polymeter_span {
+        let mut meters = ast.layers.iter().flat_map(|l| &l.bars);
+        let first = meters.next().and_then(|bar| bar.meter).unwrap_or(meter);
+        if meters.any(|bar| bar.meter.unwrap_or(meter) != first) {
+            return Err((span, "polymeter needs every bar in the same meter".into()));
+        }
+        if ast
+            .polymeter_length()
+            .is_none_or(|length| length > MAX_POLYMETER_BARS)
+        {
+            return Err((
+                span,
+                format!("polymeter track is longer than {MAX_POLYMETER_BARS} bars"),
+            ));
+        }
+    }
+    let bar_count = ast.bar_count();
+    if let Some((span, lane)) = lanes.iter().find(|(_, l)| l.bars.len() > bar_count) {
+        return Err((
+            span.clone(),
+            format!(
+                "lane '{}' has {} bars but the track only has {}",
+                lane.name,
+                lane.bars.len(),
+                bar_count
+            ),
+        ));
+    }
+    Ok(ast)
+}
+
+/// Blanks out comments with spaces, so byte offsets still match the input.
+/// `#` right after a note letter inside a bar is a sharp; anywhere else it
+/// starts a comment running to the end of the line.
 fn strip_comments(s: &str) -> String {
-    s.lines()
-        .map(|line| {
-            if let Some(idx) = line.find('#') {
-                &line[..idx]
-            } else {
-                line
+    let mut out = String::with_capacity(s.len());
+    let mut depth = 0usize;
+    let mut in_comment = false;
+    let mut prev = None;
+    for c in s.chars() {
+        if c == '\n' {
+            in_comment = false;
+        } else if !in_comment {
+            match c {
+                '(' | '{' | '[' => depth += 1,
+                ')' | '}' | ']' => depth = depth.saturating_sub(1),
+                '#' if depth == 0 || !matches!(prev, Some('A'..='G')) => in_comment = true,
+                _ => {}
             }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+        }
+        if in_comment {
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        } else {
+            out.push(c);
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+// Whitespace is dropped, except for a single space between two name or
+// number characters outside any bar. That space separates the names in a
+// song line (`A A B A`) and keywords from their arguments (`meter 3/4`).
+// Inside a bar all whitespace goes, so `(0 1)` still reads as `(01)`. Also
+// returns the input offset of every output byte.
+fn collapse_whitespace(s: &str) -> (String, Vec<usize>) {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len());
+    let mut pending_space = None;
+    let mut depth = 0usize;
+    for (idx, c) in s.char_indices() {
+        if c.is_whitespace() {
+            if depth == 0 {
+                pending_space.get_or_insert(idx);
+            }
+            continue;
+        }
+        match c {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if let Some(space) = pending_space.take()
+            && is_name_char(c)
+            && out.ends_with(is_name_char)
+        {
+            out.push(' ');
+            offsets.push(space);
+        }
+        out.push(c);
+        offsets.extend(std::iter::repeat_n(idx, c.len_utf8()));
+    }
+    (out, offsets)
+}
+
+/// Maps spans in the collapsed text back to the original notation.
+struct SourceMap<'a> {
+    source: &'a str,
+    offsets: Vec<usize>,
+}
+
+impl SourceMap<'_> {
+    fn original(&self, offset: usize) -> usize {
+        match self.offsets.get(offset) {
+            Some(&original) => original,
+            None => self.offsets.last().map_or(0, |&last| {
+                last + self.source[last..].chars().next().map_or(0, char::len_utf8)
+            }),
+        }
+    }
+
+    fn error(&self, span: Range<usize>, message: String) -> TrackParseError {
+        let start = self.original(span.start);
+        let end = if span.end > span.start {
+            let last = self.original(span.end - 1);
+            last + self.source[last..].chars().next().map_or(0, char::len_utf8)
+        } else {
+            start
+        };
+        let before = &self.source[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        TrackParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            span: start..end,
+            message,
+        }
+    }
+
+    /// First offset in the collapsed text that lies on a later line than
+    /// `offset` and starts outside every bracket opened since `from`, so a
+    /// broken multi-line bar is skipped as a whole.
+    fn next_line(&self, from: usize, offset: usize) -> usize {
+        let start = self.original(from);
+        let original = self.original(offset);
+        let mut depth = 0usize;
+        for (idx, c) in self.source[start..].char_indices() {
+            match c {
+                '(' | '{' | '[' => depth += 1,
+                ')' | '}' | ']' => depth = depth.saturating_sub(1),
+                '\n' if depth == 0 && start + idx >= original => {
+                    return self.offsets.partition_point(|&o| o <= start + idx);
+                }
+                _ => {}
+            }
+        }
+        self.offsets.len()
+    }
 }
 
-fn parse_notation(input: &str) -> Result<ParsedTrackAST, String> {
-    let input = strip_comments(input);
-    let input: String = input.chars().filter(|c| !c.is_whitespace()).collect();
-    match parse_track(&input) {
-        Ok((_, track)) => Ok(track),
-        Err(e) => Err(format!("Parse error: {:?}", e)),
+/// Parses statement by statement. After a syntax error, parsing resumes on
+/// the next line so every broken line is reported at once.
+fn parse_notation(input: &str) -> Result<ParsedTrackAST, Vec<TrackParseError>> {
+    let stripped = strip_comments(input);
+    let (text, offsets) = collapse_whitespace(&stripped);
+    let map = SourceMap {
+        source: &stripped,
+        offsets,
+    };
+
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        if text[pos..].starts_with(' ') {
+            pos += 1;
+            continue;
+        }
+        match parse_statement(&text[pos..]) {
+            Ok((rest, statement)) => {
+                let end = text.len() - rest.len();
+                statements.push((pos..end, statement));
+                pos = end;
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                let at = text.len() - e.input.len();
+                let width = e.input.chars().next().map_or(0, char::len_utf8);
+                errors.push(map.error(at..at + width, e.message()));
+                pos = map.next_line(pos, at);
+            }
+            Err(nom::Err::Incomplete(_)) => break,
+        }
     }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    expand_sections(statements).map_err(|(span, message)| vec![map.error(span, message)])
 }
 
+/// A note placed on the track's 0..1 timeline.
 #[derive(Clone, Debug)]
-struct TimelineNote {
-    pitch: u8,
-    degree: i32,
-    start: f32,
-    end: f32,
+pub struct TimelineNote {
+    pub pitch: u8,
+    pub degree: i32,
+    pub velocity: u8,
+    pub tie: bool,
+    pub slur: bool,
+    pub start: f32,
+    pub end: f32,
+    // Every condition must hold on a given loop for the note to play.
+    conditions: Vec<Condition>,
+}
+
+impl TimelineNote {
+    /// Whether the note depends on a chance or choice, so it may not play
+    /// on every loop.
+    pub fn is_conditional(&self) -> bool {
+        !self.conditions.is_empty()
+    }
+
+    fn plays(&self, seed: u64, pass: u64) -> bool {
+        self.conditions.iter().all(|c| c.holds(seed, pass))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Condition {
+    Chance {
+        id: u64,
+        percent: u8,
+    },
+    Choice {
+        id: u64,
+        option: usize,
+        count: usize,
+    },
+}
+
+impl Condition {
+    fn holds(&self, seed: u64, pass: u64) -> bool {
+        let roll = |id| crate::utils::derive_seed(crate::utils::derive_seed(seed, pass) as u64, id);
+        match *self {
+            Condition::Chance { id, percent } => roll(id) % 100 < percent as u32,
+            Condition::Choice { id, option, count } => roll(id) as usize % count == option,
+        }
+    }
+}
+
+/// Conditions enclosing the item being extracted. Each chance or choice
+/// gets its own id so repeated bars roll independently.
+#[derive(Default)]
+struct Conditions {
+    stack: Vec<Condition>,
+    next_id: u64,
+}
+
+impl Conditions {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+fn degree_at_or_below(scale: &crate::Scale, pitch: i32) -> i32 {
+    if scale.is_empty() {
+        return 0;
+    }
+    let mut degree = 0;
+    while scale.note(degree) > pitch {
+        degree -= 1;
+    }
+    while scale.note(degree + 1) <= pitch {
+        degree += 1;
+    }
+    degree
+}
+
+/// Returns the degree and pitch of a scale degree moved by `chromatic`
+/// semitones.
+fn degree_pitch(scale: &crate::Scale, degree: i32, chromatic: i32, shift: Shift) -> (i32, i32) {
+    let degree = degree + shift.degrees + shift.octaves * scale.len() as i32;
+    (degree, scale.note(degree) + chromatic)
+}
+
+/// Places a pitch class (semitones above C) in the scale's base octave. A
+/// transposition moves it along the scale from the degree at or below it.
+fn letter_pitch(scale: &crate::Scale, pitch_class: i32, shift: Shift) -> (i32, i32) {
+    let c = scale.note(0) - scale.pitch_class();
+    let pitch = c + pitch_class + shift.octaves * scale.period();
+    let below = degree_at_or_below(scale, pitch);
+    let pitch = pitch + scale.note(below + shift.degrees) - scale.note(below);
+    (degree_at_or_below(scale, pitch), pitch)
 }
 
 fn extract_notes(
@@ -195,112 +1428,449 @@ fn extract_notes(
     end: f32,
     notes: &mut Vec<TimelineNote>,
     scale: &crate::Scale,
+    shift: Shift,
+    conditions: &mut Conditions,
 ) {
+    let mut push = |(degree, pitch): (i32, i32), velocity: Option<u8>, tie, slur| {
+        notes.push(TimelineNote {
+            pitch: pitch.clamp(0, 127) as u8,
+            degree,
+            velocity: velocity.unwrap_or(DEFAULT_VELOCITY),
+            tie,
+            slur,
+            start,
+            end,
+            conditions: conditions.stack.clone(),
+        })
+    };
     match item {
         Item::Note(note) => {
-            let pitch = (scale.note(note.degree) + note.chromatic_shift).clamp(0, 127) as u8;
-            notes.push(TimelineNote {
-                pitch,
-                degree: note.degree,
-                start,
-                end,
-            });
+            let pitch = degree_pitch(scale, note.degree, note.chromatic_shift, shift);
+            push(pitch, note.velocity, note.tie, note.slur);
+        }
+        Item::Chord(chord) => {
+            for tone in chord.tones() {
+                let pitch = match chord.root {
+                    ChordRoot::Letter(root) => letter_pitch(scale, root + tone, shift),
+                    ChordRoot::Roman { degree, flat, .. } => {
+                        degree_pitch(scale, degree, tone - flat as i32, shift)
+                    }
+                };
+                push(pitch, chord.velocity, chord.tie, chord.slur);
+            }
         }
         Item::Rest => {}
         Item::Sequence(divs) => {
-            let div_span = end - start;
-            let total_weight: usize = divs.iter().map(|d| d.weight).sum();
-            let mut weight_idx = 0;
-            for subdiv in divs.iter() {
-                let sub_start = start + (weight_idx as f32 / total_weight as f32) * div_span;
-                let sub_end =
-                    start + ((weight_idx + subdiv.weight) as f32 / total_weight as f32) * div_span;
-                extract_notes(&subdiv.item, sub_start, sub_end, notes, scale);
-                weight_idx += subdiv.weight;
-            }
+            extract_sequence(divs, start, end, notes, scale, shift, conditions);
         }
         Item::Polyphony(layers) => {
             for layer_divs in layers {
-                if layer_divs.is_empty() {
-                    continue;
-                }
-                let div_span = end - start;
-                let total_weight: usize = layer_divs.iter().map(|d| d.weight).sum();
-                let mut weight_idx = 0;
-                for subdiv in layer_divs.iter() {
-                    let sub_start = start + (weight_idx as f32 / total_weight as f32) * div_span;
-                    let sub_end = start
-                        + ((weight_idx + subdiv.weight) as f32 / total_weight as f32) * div_span;
-                    extract_notes(&subdiv.item, sub_start, sub_end, notes, scale);
-                    weight_idx += subdiv.weight;
-                }
+                extract_sequence(layer_divs, start, end, notes, scale, shift, conditions);
+            }
+        }
+        Item::Shift(inner, inner_shift) => {
+            let shift = Shift {
+                octaves: shift.octaves + inner_shift.octaves,
+                degrees: shift.degrees + inner_shift.degrees,
+            };
+            extract_notes(inner, start, end, notes, scale, shift, conditions);
+        }
+        Item::Chance(inner, percent) => {
+            let id = conditions.next_id();
+            conditions.stack.push(Condition::Chance {
+                id,
+                percent: *percent,
+            });
+            extract_notes(inner, start, end, notes, scale, shift, conditions);
+            conditions.stack.pop();
+        }
+        Item::Choice(options) => {
+            let id = conditions.next_id();
+            for (option, divs) in options.iter().enumerate() {
+                conditions.stack.push(Condition::Choice {
+                    id,
+                    option,
+                    count: options.len(),
+                });
+                extract_sequence(divs, start, end, notes, scale, shift, conditions);
+                conditions.stack.pop();
+            }
+        }
+    }
+}
+
+fn extract_sequence(
+    divs: &[Division],
+    start: f32,
+    end: f32,
+    notes: &mut Vec<TimelineNote>,
+    scale: &crate::Scale,
+    shift: Shift,
+    conditions: &mut Conditions,
+) {
+    let span = end - start;
+    let total_weight: usize = divs.iter().map(|d| d.weight).sum();
+    let mut weight_idx = 0;
+    for div in divs {
+        let div_start = start + (weight_idx as f32 / total_weight as f32) * span;
+        let div_end = start + ((weight_idx + div.weight) as f32 / total_weight as f32) * span;
+        extract_notes(
+            &div.item, div_start, div_end, notes, scale, shift, conditions,
+        );
+        weight_idx += div.weight;
+    }
+}
+
+/// A named control signal sequenced on the same bar grid as the notes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lane {
+    name: String,
+    interpolation: Interpolation,
+    // (position, value), sorted by position.
+    points: Vec<(f32, f32)>,
+    value: f32,
+}
+
+impl Lane {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// The value at the playhead.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    fn value_at(&self, pos: f32) -> f32 {
+        let Some(&(_, last)) = self.points.last() else {
+            return 0.0;
+        };
+        let next = self.points.partition_point(|&(start, _)| start <= pos);
+        // Before the first point the lane still holds the end of the loop.
+        let (from_pos, from) = match next {
+            0 => (self.points[self.points.len() - 1].0 - 1.0, last),
+            i => self.points[i - 1],
+        };
+        match self.interpolation {
+            Interpolation::Step => from,
+            Interpolation::Ramp => {
+                let (to_pos, to) = self
+                    .points
+                    .get(next)
+                    .copied()
+                    .unwrap_or((self.points[0].0 + 1.0, self.points[0].1));
+                let t = (pos - from_pos) / (to_pos - from_pos).max(f32::EPSILON);
+                from + (to - from) * t.clamp(0.0, 1.0)
             }
         }
     }
 }
 
+fn extract_lane_points(divs: &[LaneDivision], start: f32, end: f32, points: &mut Vec<(f32, f32)>) {
+    let span = end - start;
+    let total_weight: usize = divs.iter().map(|d| d.weight).sum();
+    let mut weight_idx = 0;
+    for div in divs {
+        let div_start = start + (weight_idx as f32 / total_weight as f32) * span;
+        let div_end = start + ((weight_idx + div.weight) as f32 / total_weight as f32) * span;
+        match &div.item {
+            LaneItem::Value(value) => points.push((div_start, *value)),
+            LaneItem::Hold => {}
+            LaneItem::Sequence(inner) => extract_lane_points(inner, div_start, div_end, points),
+        }
+        weight_idx += div.weight;
+    }
+}
+
+/// Positions of the bar lines on the 0..1 timeline, from the first bar's
+/// start to the last bar's end, with each bar as long as its meter.
+fn bar_lines(meters: &[TimeSignature]) -> Vec<f32> {
+    let total: usize = meters.iter().map(TimeSignature::sixteenths).sum();
+    let mut position = 0;
+    let mut lines = vec![0.0];
+    for meter in meters {
+        position += meter.sixteenths();
+        lines.push(position as f32 / total as f32);
+    }
+    lines
+}
+
+const TIE_EPSILON: f32 = 1e-5;
+const SLUR_OVERLAP: f32 = 0.1;
+
+/// Merges each tied note with the same-pitch note starting where it ends,
+/// if both play under the same conditions. Expects `notes` sorted by start.
+fn join_ties(notes: &mut Vec<TimelineNote>) {
+    let mut merged = vec![false; notes.len()];
+    for i in 0..notes.len() {
+        if merged[i] {
+            continue;
+        }
+        while notes[i].tie {
+            let end = notes[i].end;
+            let from = notes.partition_point(|n| n.start < end - TIE_EPSILON);
+            let next = (from..notes.len())
+                .take_while(|&j| notes[j].start < end + TIE_EPSILON)
+                .find(|&j| {
+                    j != i
+                        && !merged[j]
+                        && notes[j].pitch == notes[i].pitch
+                        && notes[j].conditions == notes[i].conditions
+                });
+            let Some(j) = next else {
+                break;
+            };
+            merged[j] = true;
+            notes[i].end = notes[j].end;
+            notes[i].tie = notes[j].tie;
+            notes[i].slur = notes[j].slur;
+        }
+    }
+    let mut merged = merged.into_iter();
+    notes.retain(|_| !merged.next().unwrap_or(false));
+}
+
+/// Holds slurred notes slightly past their end so the next note starts
+/// while they are still gated, without running into a later note of the
+/// same pitch.
+fn overlap_slurs(notes: &mut [TimelineNote]) {
+    let mut starts: Vec<(u8, f32)> = notes.iter().map(|n| (n.pitch, n.start)).collect();
+    starts.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    for note in notes.iter_mut().filter(|n| n.slur) {
+        let mut end = note.end + (note.end - note.start) * SLUR_OVERLAP;
+        let after = note.end - TIE_EPSILON;
+        let next = starts.partition_point(|&(pitch, start)| {
+            pitch < note.pitch || (pitch == note.pitch && start < after)
+        });
+        if let Some(&(_, start)) = starts.get(next).filter(|(pitch, _)| *pitch == note.pitch) {
+            end = end.min(start);
+        }
+        note.end = end.min(1.0);
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum NoteEvent {
-    Press { pitch: u8, degree: i32 },
-    Release { pitch: u8 },
+    Press {
+        pitch: u8,
+        degree: i32,
+        velocity: u8,
+    },
+    Release {
+        pitch: u8,
+    },
 }
 
 pub struct Track {
     playhead: f32,
     note_timeline: Vec<TimelineNote>,
+    // The notes on the even grid they were written on, before the groove.
+    straight: Vec<TimelineNote>,
+    // Indices into `note_timeline`, ordered by note end.
+    ends: Vec<usize>,
+    // Reused by `play`.
+    events: Vec<NoteEvent>,
     bar_count: usize,
+    meters: Vec<TimeSignature>,
+    tuning: crate::Tuning,
+    seed: u64,
+    // Loop counter, so chances and choices are rolled again on every pass.
+    pass: u64,
+    lanes: Vec<Lane>,
 }
 
-impl Track {
-    pub fn set_playhead(&mut self, phase: f32) {
-        self.playhead = phase.rem_euclid(1.0);
+impl Track {
+    pub fn set_playhead(&mut self, phase: f32) {
+        self.playhead = phase.rem_euclid(1.0);
+        for lane in &mut self.lanes {
+            lane.value = lane.value_at(self.playhead);
+        }
+    }
+
+    pub fn set_tuning(&mut self, tuning: crate::Tuning) {
+        self.tuning = tuning;
+    }
+
+    /// Moves every note by the groove's timing and velocity offsets,
+    /// replacing any groove set before.
+    pub fn set_groove(&mut self, groove: &crate::Groove) {
+        // Bar lines in 16ths; the groove restarts at each of them.
+        let mut lines = vec![0];
+        for meter in &self.meters {
+            lines.push(lines[lines.len() - 1] + meter.sixteenths());
+        }
+        let total = lines[lines.len() - 1] as f32;
+        let locate = |pos: f32| {
+            let pos = pos * total;
+            let bar = lines[1..]
+                .partition_point(|&end| end as f32 <= pos)
+                .min(self.meters.len() - 1);
+            let start = lines[bar] as f32;
+            (start, pos - start, self.meters[bar].sixteenths())
+        };
+        let shift = |pos: f32| {
+            let (start, local, steps) = locate(pos);
+            ((start + groove.shift(local, steps)) / total).clamp(0.0, 1.0)
+        };
+        // The shift never reorders positions, so the timeline stays sorted.
+        self.note_timeline = self
+            .straight
+            .iter()
+            .map(|note| {
+                let (_, local, steps) = locate(note.start);
+                TimelineNote {
+                    start: shift(note.start),
+                    end: shift(note.end),
+                    velocity: groove.accent(local, steps, note.velocity),
+                    ..note.clone()
+                }
+            })
+            .collect();
+        self.index_timeline();
     }
 
-    pub fn parse(notation: &str, scale: &crate::Scale) -> Result<Self, String> {
-        let ast = parse_notation(notation)?;
-        let mut events = Vec::new();
+    pub fn frequency(&self, pitch: u8) -> f32 {
+        self.tuning.frequency(pitch as i32)
+    }
+
+    /// Seeds the random chances and choices and restarts the loop count, so
+    /// the same seed always plays the same sequence of variations.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.pass = 0;
+    }
 
-        let bar_count = ast.layers.iter().map(|l| l.bars.len()).max().unwrap_or(1);
+    pub fn parse(notation: &str, scale: &crate::Scale) -> Result<Self, Vec<TrackParseError>> {
+        Ok(Self::from_ast(&parse_notation(notation)?, scale))
+    }
 
-        for layer in &ast.layers {
-            let num_bars = layer.bars.len();
+    pub fn from_ast(ast: &ParsedTrackAST, scale: &crate::Scale) -> Self {
+        let mut events = Vec::new();
 
-            for (bar_idx, bar) in layer.bars.iter().enumerate() {
-                let bar_start = bar_idx as f32 / num_bars as f32;
-                let bar_end = (bar_idx + 1) as f32 / num_bars as f32;
-                let bar_span = bar_end - bar_start;
+        let bar_count = ast.bar_count().max(1);
 
-                let total_weight: usize = bar.divisions.iter().map(|d| d.weight).sum();
-                let mut weight_idx = 0;
+        let mut meters = ast.meters();
+        if meters.is_empty() {
+            meters.push(ast.meter);
+        }
 
-                for division in &bar.divisions {
-                    let div_start =
-                        bar_start + (weight_idx as f32 / total_weight as f32) * bar_span;
-                    let div_end = bar_start
-                        + ((weight_idx + division.weight) as f32 / total_weight as f32) * bar_span;
-                    extract_notes(&division.item, div_start, div_end, &mut events, scale);
-                    weight_idx += division.weight;
+        let mut conditions = Conditions::default();
+        for layer in ast.layers.iter().filter(|l| !l.bars.is_empty()) {
+            // Stretched layers spread their bars over the track, each as
+            // long as its meter; polymeter layers loop to fill it, one bar
+            // per track bar.
+            let (bars, lines): (Vec<_>, _) = match ast.layer_mode {
+                LayerMode::Stretch => {
+                    let meters: Vec<_> = layer
+                        .bars
+                        .iter()
+                        .map(|bar| bar.meter.unwrap_or(ast.meter))
+                        .collect();
+                    (layer.bars.iter().collect(), bar_lines(&meters))
                 }
+                LayerMode::Polymeter { .. } => (
+                    layer.bars.iter().cycle().take(bar_count).collect(),
+                    bar_lines(&meters),
+                ),
+            };
+            for (slot, bar) in bars.into_iter().enumerate() {
+                extract_sequence(
+                    &bar.divisions,
+                    lines[slot],
+                    lines[slot + 1],
+                    &mut events,
+                    scale,
+                    Shift::default(),
+                    &mut conditions,
+                );
             }
         }
 
+        // Lanes shorter than the track repeat to fill it.
+        let lines = bar_lines(&meters);
+        let lanes = ast
+            .lanes
+            .iter()
+            .map(|parsed| {
+                let mut points = Vec::new();
+                for bar_idx in 0..meters.len() {
+                    let Some(bar) = parsed.bars.get(bar_idx % parsed.bars.len().max(1)) else {
+                        break;
+                    };
+                    extract_lane_points(bar, lines[bar_idx], lines[bar_idx + 1], &mut points);
+                }
+                let mut lane = Lane {
+                    name: parsed.name.clone(),
+                    interpolation: parsed.interpolation,
+                    points,
+                    value: 0.0,
+                };
+                lane.value = lane.value_at(0.0);
+                lane
+            })
+            .collect();
+
         events.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+        join_ties(&mut events);
+        overlap_slurs(&mut events);
 
-        Ok(Track {
+        let mut track = Track {
             playhead: 0.0,
+            straight: events.clone(),
             note_timeline: events,
+            ends: Vec::new(),
+            events: Vec::new(),
             bar_count,
-        })
+            meters,
+            tuning: crate::Tuning::default(),
+            seed: 0,
+            pass: 0,
+            lanes,
+        };
+        track.index_timeline();
+        track
     }
 
     pub fn bar_count(&self) -> usize {
         self.bar_count
     }
 
-    pub fn play(&mut self, to: f32) -> Vec<NoteEvent> {
+    /// Time signature of every bar.
+    pub fn meters(&self) -> &[TimeSignature] {
+        &self.meters
+    }
+
+    /// Length of the track in quarter notes, the beats counted by the
+    /// tempo.
+    pub fn beats(&self) -> f32 {
+        self.meters.iter().map(TimeSignature::quarters).sum()
+    }
+
+    /// The notes in order of their start, after the groove.
+    pub fn notes(&self) -> &[TimelineNote] {
+        &self.note_timeline
+    }
+
+    pub fn lanes(&self) -> &[Lane] {
+        &self.lanes
+    }
+
+    pub fn lane(&self, name: &str) -> Option<&Lane> {
+        self.lanes.iter().find(|lane| lane.name == name)
+    }
+
+    /// Moves the playhead to `to` and returns the notes pressed and released
+    /// on the way, in the order they were crossed. The events are kept in a
+    /// buffer owned by the track, so playing never allocates.
+    pub fn play(&mut self, to: f32) -> &[NoteEvent] {
         let to = to.fract(); // Wrap to 0.0..1.0 range
         if to == self.playhead {
-            return Vec::new();
+            self.events.clear();
+            return &self.events;
         }
 
         // Infer direction based on distance
@@ -321,120 +1891,121 @@ impl Track {
         self.advance_with_direction(to, is_forward)
     }
 
-    // fn crossed(pos: f32, from: f32, to: f32, inclusive: bool) -> bool {
-    //     // Did we cross `pos` when moving from `from` to `to`?
-    //     // Handles wrap-around at 1.0
-    //     if from <= to {
-    //         // Normal: moving forward without wrap
-    //         if inclusive {
-    //             from <= pos && pos <= to
-    //         } else {
-    //             from < pos && pos <= to
-    //         }
-    //     } else {
-    //         // Wrap: moving from high to low (crossing 1.0 -> 0.0)
-    //         if inclusive {
-    //             (pos >= from && pos <= 1.0) || (pos >= 0.0 && pos <= to)
-    //         } else {
-    //             (pos > from && pos <= 1.0) || (pos >= 0.0 && pos <= to)
-    //         }
-    //     }
-    // }
-
-    #[allow(clippy::collapsible_else_if)]
-    pub(crate) fn advance_with_direction(&mut self, to: f32, forward: bool) -> Vec<NoteEvent> {
+    /// The events from the last call to `play`.
+    pub fn events(&self) -> &[NoteEvent] {
+        &self.events
+    }
+
+    pub(crate) fn advance_with_direction(&mut self, to: f32, forward: bool) -> &[NoteEvent] {
         // Normalize to [0, 1]
         let from = self.playhead;
         let to = to.rem_euclid(1.0);
 
-        let mut events = Vec::new();
-        for note in &self.note_timeline {
-            let note_start = note.start;
-            let note_end = note.end;
-            // dbg!(note_start, note_end, from, to);
-            if forward {
-                if to < from {
-                    // looped around
-                    // range of track: 0---1
-                    // range between from..to: ===
-                    // range of note start..end: s###e
-                    // 0---------------------1
-                    // ###e==t---------f==s###
-                    if note_start >= from || note_start < to {
-                        events.push(NoteEvent::Press {
-                            pitch: note.pitch,
-                            degree: note.degree,
-                        });
-                    }
-                    // ======t------s##f##e===
-                    if note_end >= from || note_end < to {
-                        events.push(NoteEvent::Release { pitch: note.pitch });
-                    }
-                } else {
-                    // ------f=s#######t##e----
-                    if note_start >= from && note_start < to {
-                        events.push(NoteEvent::Press {
-                            pitch: note.pitch,
-                            degree: note.degree,
-                        });
-                    }
-                    // ----s##f#####e==t------
-                    if note_end >= from && note_end < to {
-                        events.push(NoteEvent::Release { pitch: note.pitch });
-                    }
-                }
-            } else {
-                if to > from {
-                    // looped around
-                    if note_start <= from || note_start > to {
-                        events.push(NoteEvent::Press {
-                            pitch: note.pitch,
-                            degree: note.degree,
-                        });
-                    }
-                    if note_end <= from || note_end > to {
-                        events.push(NoteEvent::Release { pitch: note.pitch });
-                    }
-                } else {
-                    if note_start <= from && note_start > to {
-                        events.push(NoteEvent::Press {
-                            pitch: note.pitch,
-                            degree: note.degree,
-                        });
-                    }
-                    if note_end <= from && note_end > to {
-                        events.push(NoteEvent::Release { pitch: note.pitch });
-                    }
-                }
+        // Positions past the wrap point belong to the neighbouring pass.
+        let wrapped = if forward { to < from } else { to > from };
+        let next_pass = match (wrapped, forward) {
+            (false, _) => self.pass,
+            (true, true) => self.pass.wrapping_add(1),
+            (true, false) => self.pass.wrapping_sub(1),
+        };
+
+        // Moving forward crosses positions in from..to, backward those in
+        // to..=from. A move over the loop point is split in two.
+        self.events.clear();
+        match (wrapped, forward) {
+            (false, true) => self.sweep(from, to, true, self.pass),
+            (false, false) => self.sweep(to, from, false, self.pass),
+            (true, true) => {
+                self.sweep(from, f32::INFINITY, true, self.pass);
+                self.sweep(f32::NEG_INFINITY, to, true, next_pass);
+            }
+            (true, false) => {
+                self.sweep(f32::NEG_INFINITY, from, false, self.pass);
+                self.sweep(to, f32::INFINITY, false, next_pass);
             }
         }
+        self.pass = next_pass;
+        for lane in &mut self.lanes {
+            lane.value = lane.value_at(to);
+        }
         self.playhead = to;
-        events
-        // let to = if to == 0.0 && forward { 1.0 } else { to };
-
-        // if (to - self.playhead).abs() < 1e-9 {
-        //     return Vec::new();
-        // }
+        &self.events
+    }
 
-        // let mut events = Vec::new();
-        // let (start, end) = if forward {
-        //     (self.playhead, to)
-        // } else {
-        //     (to, self.playhead)
-        // };
+    /// Appends the presses and releases between `low` and `high`, found by
+    /// binary search on the start and end orders of the timeline. Forward
+    /// sweeps include `low` and run upwards; backward sweeps include `high`
+    /// and run downwards.
+    fn sweep(&mut self, low: f32, high: f32, forward: bool, pass: u64) {
+        let Track {
+            note_timeline: notes,
+            ends,
+            events,
+            seed,
+            ..
+        } = self;
+        let before = |pos: f32, bound: f32| if forward { pos < bound } else { pos <= bound };
+        let starts = notes.partition_point(|n| before(n.start, low))
+            ..notes.partition_point(|n| before(n.start, high));
+        let ends = &ends[ends.partition_point(|&i| before(notes[i].end, low))
+            ..ends.partition_point(|&i| before(notes[i].end, high))];
+        let press_at = |k: usize| match forward {
+            true => starts.start + k,
+            false => starts.end - 1 - k,
+        };
+        let release_at = |k: usize| match forward {
+            true => ends[k],
+            false => ends[ends.len() - 1 - k],
+        };
 
-        // for note in &self.note_timeline {
-        //     // Press: include the boundary, Release: exclude the from boundary
-        //     if Self::crossed(note.start, start, end, true) {
-        //         events.push(NoteEvent::Press { pitch: note.pitch });
-        //     }
-        //     if Self::crossed(note.end, start, end, false) {
-        //         events.push(NoteEvent::Release { pitch: note.pitch });
-        //     }
-        // }
+        let (mut pressed, mut released) = (0, 0);
+        loop {
+            let press = (pressed < starts.len()).then(|| press_at(pressed));
+            let release = (released < ends.len()).then(|| release_at(released));
+            let release_first = match (press, release) {
+                (None, None) => break,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (Some(p), Some(r)) => {
+                    let (start, end) = (notes[p].start, notes[r].end);
+                    // At the same position other notes are released before
+                    // new ones are pressed, but a note without length is
+                    // pressed before its own release.
+                    if start == end {
+                        notes[r].start < end
+                    } else {
+                        (end < start) == forward
+                    }
+                }
+            };
+            let (note, event) = if release_first {
+                released += 1;
+                let note = &notes[release.unwrap()];
+                (note, NoteEvent::Release { pitch: note.pitch })
+            } else {
+                pressed += 1;
+                let note = &notes[press.unwrap()];
+                let event = NoteEvent::Press {
+                    pitch: note.pitch,
+                    degree: note.degree,
+                    velocity: note.velocity,
+                };
+                (note, event)
+            };
+            if note.plays(*seed, pass) {
+                events.push(event);
+            }
+        }
+    }
 
-        // self.playhead = to;
-        // events
+    /// Orders the timeline by note end for `sweep` and makes room for every
+    /// note to be pressed and released in one call to `play`.
+    fn index_timeline(&mut self) {
+        let notes = &self.note_timeline;
+        let mut ends: Vec<usize> = (0..notes.len()).collect();
+        ends.sort_by(|&a, &b| notes[a].end.total_cmp(&notes[b].end));
+        self.ends = ends;
+        self.events = Vec::with_capacity(notes.len() * 2);
     }
 }
 
@@ -886,6 +2457,63 @@ mod tests {
         assert!(!events.is_empty(), "Expected events when moving backward");
     }
 
+    #[test]
+    fn test_play_long_song_in_order() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("(0/1/2/0)x64", &scale).unwrap();
+        let mut held = [false; 128];
+        let mut presses = 0;
+        // Two loops in uneven steps, so some steps cross several notes and
+        // the loop point.
+        let mut phase = 0.0;
+        while phase < 2.0 {
+            phase += 0.0037;
+            for &event in track.play(phase) {
+                match event {
+                    NoteEvent::Press { pitch, .. } => {
+                        assert!(!held[pitch as usize], "{} pressed twice", pitch);
+                        held[pitch as usize] = true;
+                        presses += 1;
+                    }
+                    NoteEvent::Release { pitch } => {
+                        assert!(held[pitch as usize], "{} released twice", pitch);
+                        held[pitch as usize] = false;
+                    }
+                }
+            }
+        }
+        assert_eq!(presses, 2 * 256 + 1);
+    }
+
+    #[test]
+    fn test_play_reuses_event_buffer() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("{0&2&4}x8\n& (0/1/2/3)x4", &scale).unwrap();
+        let buffer = (track.events.as_ptr(), track.events.capacity());
+        for step in 1..=100 {
+            track.play(step as f32 * 0.31);
+            track.play(step as f32 * 0.31 - 0.2);
+        }
+        assert_eq!((track.events.as_ptr(), track.events.capacity()), buffer);
+
+        // Crossing the start of a note and the end of another at once.
+        let mut track = Track::parse("(0/0)", &scale).unwrap();
+        track.play(0.4);
+        let events = track.play(0.6).to_vec();
+        assert_eq!(
+            events,
+            vec![
+                NoteEvent::Release { pitch: 48 },
+                NoteEvent::Press {
+                    pitch: 48,
+                    degree: 0,
+                    velocity: DEFAULT_VELOCITY
+                }
+            ]
+        );
+        assert_eq!(track.events(), events);
+    }
+
     #[test]
     fn test_track_multi_bar_sequence() {
         let scale = crate::scale::cmaj();
@@ -953,4 +2581,587 @@ mod tests {
             _ => panic!("Expected polyphony"),
         }
     }
+
+    fn pressed_pitches(notation: &str, scale: &crate::Scale, from: f32) -> Vec<u8> {
+        let mut track = Track::parse(notation, scale).unwrap();
+        track.set_playhead(from);
+        let mut pitches: Vec<u8> = track
+            .play(from + 0.25)
+            .iter()
+            .filter_map(|&e| match e {
+                NoteEvent::Press { pitch, .. } => Some(pitch),
+                _ => None,
+            })
+            .collect();
+        pitches.sort();
+        pitches
+    }
+
+    #[test]
+    fn test_parse_letter_chords() {
+        let scale = crate::scale::cmaj();
+        assert_eq!(pressed_pitches("(Cm7)", &scale, 0.0), vec![48, 51, 55, 58]);
+        assert_eq!(
+            pressed_pitches("(G7/B)", &scale, 0.0),
+            vec![47, 55, 59, 62, 65]
+        );
+        assert_eq!(
+            pressed_pitches("(Bbmaj7)", &scale, 0.0),
+            vec![58, 62, 65, 69]
+        );
+        assert_eq!(
+            pressed_pitches("(C#m) # comment", &scale, 0.0),
+            vec![49, 52, 56]
+        );
+
+        // A bass note only binds when it isn't the start of another chord.
+        assert_eq!(pressed_pitches("(C/Gm)", &scale, 0.5), vec![55, 58, 62]);
+    }
+
+    #[test]
+    fn test_parse_roman_chords() {
+        let scale = crate::scale::cmin();
+        assert_eq!(pressed_pitches("(I/iv7)", &scale, 0.0), vec![48, 52, 55]);
+        assert_eq!(
+            pressed_pitches("(I/iv7)", &scale, 0.5),
+            vec![53, 56, 60, 63]
+        );
+        assert_eq!(pressed_pitches("(bVII)", &scale, 0.0), vec![57, 61, 64]);
+    }
+
+    #[test]
+    fn test_chord_inversion_and_spread() {
+        let scale = crate::scale::cmaj();
+        assert_eq!(pressed_pitches("(C@1)", &scale, 0.0), vec![52, 55, 60]);
+        assert_eq!(pressed_pitches("(C@2)", &scale, 0.0), vec![55, 60, 64]);
+        assert_eq!(pressed_pitches("(C%)", &scale, 0.0), vec![48, 55, 64]);
+    }
+
+    #[test]
+    fn test_letter_chord_reports_scale_degree() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("(Am)", &scale).unwrap();
+        let mut degrees: Vec<i32> = track
+            .play(0.5)
+            .iter()
+            .filter_map(|&e| match e {
+                NoteEvent::Press { degree, .. } => Some(degree),
+                _ => None,
+            })
+            .collect();
+        degrees.sort();
+        assert_eq!(degrees, vec![5, 7, 9]);
+    }
+
+    fn pressed_velocities(notation: &str) -> Vec<u8> {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse(notation, &scale).unwrap();
+        let mut velocities = Vec::new();
+        for step in 1..=20 {
+            for &event in track.play(step as f32 / 20.0 - 0.001) {
+                if let NoteEvent::Press { velocity, .. } = event {
+                    velocities.push(velocity);
+                }
+            }
+        }
+        velocities
+    }
+
+    #[test]
+    fn test_parse_velocity_suffixes() {
+        assert_eq!(
+            pressed_velocities("(0!/1./2^64/3+!**)"),
+            vec![127, 40, 64, 127]
+        );
+        assert_eq!(pressed_velocities("(0/1)"), vec![100, 100]);
+    }
+
+    #[test]
+    fn test_group_velocity_keeps_inner_values() {
+        assert_eq!(pressed_velocities("((0/1^20)!)"), vec![127, 20]);
+        assert_eq!(pressed_velocities("(Cm.)"), vec![40, 40, 40]);
+    }
+
+    fn timeline(notation: &str) -> Vec<(u8, f32, f32)> {
+        let scale = crate::scale::cmaj();
+        Track::parse(notation, &scale)
+            .unwrap()
+            .note_timeline
+            .iter()
+            .map(|n| (n.pitch, n.start, n.end))
+            .collect()
+    }
+
+    fn assert_spans(notation: &str, expected: &[(u8, f32, f32)]) {
+        let got = timeline(notation);
+        assert_eq!(got.len(), expected.len(), "{}: {:?}", notation, got);
+        for (g, e) in got.iter().zip(expected) {
+            assert!(
+                g.0 == e.0 && (g.1 - e.1).abs() < 1e-4 && (g.2 - e.2).abs() < 1e-4,
+                "{}: {:?}",
+                notation,
+                got
+            );
+        }
+    }
+
+    #[test]
+    fn test_tie_merges_same_pitch_notes() {
+        assert_spans("(0~/0/1)", &[(48, 0.0, 2.0 / 3.0), (50, 2.0 / 3.0, 1.0)]);
+        assert_spans(
+            "(0/2~)(2/3)",
+            &[(48, 0.0, 0.25), (52, 0.25, 0.75), (53, 0.75, 1.0)],
+        );
+        assert_spans("(0~/0~/0)", &[(48, 0.0, 1.0)]);
+        assert_spans("(0~/1)", &[(48, 0.0, 0.5), (50, 0.5, 1.0)]);
+        assert_eq!(timeline("(Cm~/Cm)").len(), 3);
+    }
+
+    #[test]
+    fn test_slur_overlaps_next_note() {
+        assert_spans("(0=/1)", &[(48, 0.0, 0.55), (50, 0.5, 1.0)]);
+        assert_spans("(0=/0)", &[(48, 0.0, 0.5), (48, 0.5, 1.0)]);
+        assert_spans("((0/1)=)", &[(48, 0.0, 0.55), (50, 0.5, 1.0)]);
+    }
+
+    #[test]
+    fn test_groove_shifts_notes() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("((0/1/2/3)/_/_/_)(0)", &scale).unwrap();
+        let spans = |track: &Track| -> Vec<(f32, f32, u8)> {
+            track
+                .note_timeline
+                .iter()
+                .map(|n| (n.start * 32.0, n.end * 32.0, n.velocity))
+                .collect()
+        };
+
+        let swing = crate::Groove::from_text("soft", "0\n0 -30\n")
+            .unwrap()
+            .with_swing(75.0);
+        track.set_groove(&swing);
+        track.set_groove(&swing);
+        assert_eq!(
+            spans(&track),
+            vec![
+                (0.0, 1.5, 100),
+                (1.5, 2.0, 70),
+                (2.0, 3.5, 100),
+                (3.5, 4.0, 70),
+                (16.0, 32.0, 100),
+            ]
+        );
+
+        track.set_groove(&crate::Groove::straight());
+        assert_eq!(spans(&track)[1], (1.0, 2.0, 100));
+    }
+
+    fn bar_pitches(notation: &str) -> Vec<u8> {
+        timeline(notation)
+            .into_iter()
+            .map(|(pitch, ..)| pitch)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_bar_repetition() {
+        let scale = crate::scale::cmaj();
+        let track = Track::parse("(0/1/2)x4", &scale).unwrap();
+        assert_eq!(track.bar_count(), 4);
+        assert_eq!(track.note_timeline.len(), 12);
+        assert_eq!(bar_pitches("(0)x2(1)"), vec![48, 48, 50]);
+    }
+
+    #[test]
+    fn test_parse_sections_and_song_line() {
+        let notation = "
+            # verse and chorus
+            A = (0)(1)
+            B = (2)x2
+            A A B A
+        ";
+        assert_eq!(bar_pitches(notation), vec![48, 50, 48, 50, 52, 52, 48, 50]);
+        assert_eq!(bar_pitches("Intro Intro\nIntro = (4)"), vec![55, 55]);
+        assert_eq!(bar_pitches("A (1) A\nA = (0)"), vec![48, 50, 48]);
+        assert_eq!(
+            bar_pitches("A A B A# chorus\nA = (0)\nB = (1) # (2"),
+            vec![48, 48, 50, 48]
+        );
+        assert_eq!(bar_pitches("(0 1)\n{0\n & 2}"), bar_pitches("(01){0&2}"));
+    }
+
+    #[test]
+    fn test_layers_stretch_by_default() {
+        assert_spans(
+            "(0)(1)(2)\n& (4)(5)",
+            &[
+                (48, 0.0, 1.0 / 3.0),
+                (55, 0.0, 0.5),
+                (50, 1.0 / 3.0, 2.0 / 3.0),
+                (57, 0.5, 1.0),
+                (52, 2.0 / 3.0, 1.0),
+            ],
+        );
+        assert_eq!(bar_pitches("& (0) & A\nA = (1)"), vec![48, 50]);
+    }
+
+    #[test]
+    fn test_polymeter_layers_loop_independently() {
+        let scale = crate::scale::cmaj();
+        let notation = "polymeter\n(0)(1)(2)\n& (4)(5)";
+        assert_eq!(Track::parse(notation, &scale).unwrap().bar_count(), 6);
+        assert_eq!(
+            bar_pitches(notation),
+            vec![48, 55, 50, 57, 52, 55, 48, 57, 50, 55, 52, 57]
+        );
+
+        let track = Track::parse("polymeter 4\n(0)(1)(2)", &scale).unwrap();
+        assert_eq!(track.bar_count(), 4);
+        assert_eq!(bar_pitches("polymeter 4\n(0)(1)(2)"), vec![48, 50, 52, 48]);
+    }
+
+    #[test]
+    fn test_polymeter_errors() {
+        let errors = parse_errors("(0)\n&");
+        assert_eq!(
+            (errors[0].line, errors[0].message.as_str()),
+            (2, "layer has no bars")
+        );
+        assert_eq!(parse_errors("polymeter 0\n(0)")[0].column, 11);
+        assert_eq!(parse_errors("polymeter\npolymeter\n(0)")[0].line, 2);
+        let errors = parse_errors("polymeter\n(0)(1)\n& (2)x3\namp: (0)x7");
+        assert!(errors[0].message.contains("only has 6"));
+
+        let errors = parse_errors("(0)\npolymeter 4097");
+        assert_eq!((errors[0].line, errors[0].span.clone()), (2, 4..18));
+        assert_eq!(
+            errors[0].message,
+            "polymeter track is longer than 4096 bars"
+        );
+        let layers = [
+            "(0)x1021", "(0)x1019", "(0)x1013", "(0)x1009", "(0)x997", "(0)x991",
+        ];
+        let errors = parse_errors(&format!("polymeter\n{}", layers.join("\n& ")));
+        assert_eq!(errors[0].line, 1);
+        assert!(parse_errors("polymeter 4096\n(0)").is_empty());
+
+        // ASTs built in code are cut off at the same length.
+        let mut ast: ParsedTrackAST = "polymeter\n(0)".parse().unwrap();
+        ast.layer_mode = LayerMode::Polymeter {
+            length: Some(usize::MAX),
+        };
+        let track = Track::from_ast(&ast, &crate::scale::cmaj());
+        assert_eq!(track.bar_count(), MAX_POLYMETER_BARS);
+    }
+
+    #[test]
+    fn test_time_signatures() {
+        assert_spans(
+            "3/4(0)(1/2)",
+            &[
+                (48, 0.0, 12.0 / 28.0),
+                (50, 12.0 / 28.0, 20.0 / 28.0),
+                (52, 20.0 / 28.0, 1.0),
+            ],
+        );
+        let scale = crate::scale::cmaj();
+        let track = Track::parse("meter 7/8\n(0)(1)\n3/4(2)", &scale).unwrap();
+        let seven = TimeSignature::new(7, 8).unwrap();
+        let three = TimeSignature::new(3, 4).unwrap();
+        for (beats, unit) in [(4, 0), (0, 4), (3, 32), (4, 3), (u32::MAX, 16)] {
+            assert_eq!(TimeSignature::new(beats, unit), None);
+        }
+        assert_eq!(track.meters(), &[seven, seven, three]);
+        assert_eq!(track.beats(), 10.0);
+
+        // A signature after a repeat count starts a new bar, even in a section.
+        let track = Track::parse("A = (0)x2 3/4(1)\nA", &scale).unwrap();
+        assert_eq!(track.bar_count(), 3);
+        assert_eq!(track.beats(), 11.0);
+        // Stretched layers follow the bar lines of the longest layer.
+        let track = Track::parse("3/4(0)(1)\n& 7/8(2)", &scale).unwrap();
+        assert_eq!(track.beats(), 7.0);
+        assert_eq!(timeline("3/4(0)(1)\n& 7/8(2)")[1], (52, 0.0, 1.0));
+
+        let track = Track::parse("polymeter\nmeter 3/4\n(0)(1)\n& (2)x3", &scale).unwrap();
+        assert_eq!(track.beats(), 18.0);
+        let track = Track::parse("polymeter\n5/8(0)", &scale).unwrap();
+        assert_eq!((track.bar_count(), track.beats()), (1, 2.5));
+    }
+
+    #[test]
+    fn test_groove_restarts_at_bar_lines() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("7/16(0/1/2/3/4/5/6)(0)", &scale).unwrap();
+        track.set_groove(&crate::Groove::straight().with_swing(75.0));
+        let starts: Vec<f32> = track
+            .note_timeline
+            .iter()
+            .map(|n| (n.start * 46.0).round() / 2.0)
+            .collect();
+        assert_eq!(starts, vec![0.0, 1.5, 2.0, 3.5, 4.0, 5.5, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn test_time_signature_errors() {
+        let errors = parse_errors("3/0(0)");
+        assert!(
+            errors[0]
+                .message
+                .ends_with("expected a note value of 1, 2, 4, 8 or 16")
+        );
+        assert_eq!(parse_errors("(0)0/4(0)")[0].column, 4);
+        assert!(
+            parse_errors("meter 4\n(0)")[0]
+                .message
+                .ends_with("expected '/'")
+        );
+        let errors = parse_errors("meter 3/4\nmeter 4/4\n(0)");
+        assert_eq!(
+            (errors[0].line, errors[0].message.as_str()),
+            (2, "meter is set twice")
+        );
+        let errors = parse_errors("polymeter\n(0)\n& 3/4(1)");
+        assert_eq!(
+            (errors[0].line, errors[0].message.as_str()),
+            (1, "polymeter needs every bar in the same meter")
+        );
+    }
+
+    #[test]
+    fn test_unknown_or_empty_sections_fail() {
+        let scale = crate::scale::cmaj();
+        assert!(Track::parse("A = (0)\nA C", &scale).is_err());
+        assert!(Track::parse("A = (0)", &scale).is_err());
+    }
+
+    #[test]
+    fn test_octave_markers() {
+        assert_eq!(bar_pitches("('0/,0/''0)"), vec![60, 36, 72]);
+        assert_eq!(bar_pitches("(0/>1/2/<3)"), vec![48, 62, 64, 53]);
+        assert_eq!(bar_pitches("(>0/'1/2)(0)"), vec![60, 74, 64, 48]);
+        assert_eq!(bar_pitches("('C/,G7)"), vec![60, 64, 67, 43, 47, 50, 53]);
+    }
+
+    #[test]
+    fn test_group_transposition() {
+        assert_eq!(bar_pitches("((0/2/4)+3)"), vec![53, 57, 60]);
+        assert_eq!(bar_pitches("(0/1)-2"), vec![45, 47]);
+        assert_eq!(bar_pitches("(0)+1x2"), vec![50, 50]);
+        assert_eq!(bar_pitches("({C}+1)"), vec![50, 53, 57]);
+        assert_eq!(bar_pitches("(>(0/1)+7)"), vec![72, 74]);
+    }
+
+    /// Plays `loops` passes in quarter steps, returning the pitches pressed
+    /// on each pass and checking every press is released.
+    fn passes(notation: &str, seed: u64, loops: usize) -> Vec<Vec<u8>> {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse(notation, &scale).unwrap();
+        track.set_seed(seed);
+        (0..loops)
+            .map(|_| {
+                let mut pressed = Vec::new();
+                let mut held = 0;
+                for step in 1..=4 {
+                    for &event in track.play(step as f32 * 0.25) {
+                        match event {
+                            NoteEvent::Press { pitch, .. } => {
+                                pressed.push(pitch);
+                                held += 1;
+                            }
+                            NoteEvent::Release { .. } => held -= 1,
+                        }
+                    }
+                }
+                assert_eq!(held, 0, "unbalanced events in {}", notation);
+                pressed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_note_chance() {
+        assert!(passes("(0?0/1)", 1, 16).iter().all(|p| p == &[50]));
+        assert!(passes("(0?100/1)", 1, 16).iter().all(|p| p == &[48, 50]));
+
+        let played = passes("(0?/1)", 1, 64)
+            .iter()
+            .filter(|p| p.contains(&48))
+            .count();
+        assert!(played > 16 && played < 48, "played {} of 64", played);
+
+        for pass in passes("((0/1)?50)", 7, 32) {
+            assert!(pass.is_empty() || pass == [48, 50]);
+        }
+    }
+
+    #[test]
+    fn test_random_choice() {
+        let passes = passes("([0|2|(4/5)]/6)", 3, 64);
+        for pass in &passes {
+            assert!(
+                [&[48, 59][..], &[52, 59], &[55, 57, 59]].contains(&pass.as_slice()),
+                "{:?}",
+                pass
+            );
+        }
+        for first in [48, 52, 55] {
+            assert!(passes.iter().any(|p| p[0] == first));
+        }
+    }
+
+    #[test]
+    fn test_variation_follows_seed() {
+        let notation = "([0|1|2|3]/[4|5]?70)";
+        assert_eq!(passes(notation, 42, 32), passes(notation, 42, 32));
+        assert_ne!(passes(notation, 42, 32), passes(notation, 43, 32));
+
+        // Each repetition of a bar rolls on its own.
+        let bars = &passes("([0|1])x8", 5, 1)[0];
+        assert_eq!(bars.len(), 8);
+        assert!(bars.iter().any(|&p| p != bars[0]));
+    }
+
+    fn onsets(notation: &str) -> Vec<f32> {
+        timeline(notation)
+            .into_iter()
+            .map(|(_, start, _)| start * 8.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_euclidean_patterns() {
+        let hits = |k, n, r| -> String {
+            euclidean_pattern(k, n, r)
+                .into_iter()
+                .map(|hit| if hit { 'x' } else { '.' })
+                .collect()
+        };
+        assert_eq!(hits(3, 8, 0), "x..x..x.");
+        assert_eq!(hits(5, 8, 0), "x.xx.xx.");
+        assert_eq!(hits(3, 8, 1), "..x..x.x");
+        assert_eq!(hits(0, 4, 0), "....");
+        assert_eq!(hits(6, 4, 0), "xxxx");
+        assert_eq!(hits(3, 8, 17), hits(3, 8, 1));
+        assert_eq!(hits(1, 4, usize::MAX), hits(1, 4, 3));
+    }
+
+    #[test]
+    fn test_parse_euclidean_rhythms() {
+        assert_eq!(onsets("(e(3,8))"), vec![0.0, 3.0, 6.0]);
+        assert_eq!(onsets("(e(3,8,1))"), vec![2.0, 5.0, 7.0]);
+        assert_eq!(bar_pitches("(2e(3,8))"), vec![52, 52, 52]);
+
+        let kit = timeline("({0e(4,8)&2e(3,8)&4e(2,8,2)})");
+        assert_eq!(kit.len(), 9);
+        assert!(kit.contains(&(55, 0.25, 0.375)));
+        assert!(kit.contains(&(52, 0.375, 0.5)));
+
+        let scale = crate::scale::cmaj();
+        assert!(Track::parse("(e(3,0))", &scale).is_err());
+    }
+
+    fn parse_errors(notation: &str) -> Vec<TrackParseError> {
+        let scale = crate::scale::cmaj();
+        Track::parse(notation, &scale).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let errors = parse_errors("# intro\n(0/1)\n  (0 / 2 & 4)");
+        assert_eq!(errors.len(), 1);
+        let error = &errors[0];
+        assert_eq!((error.line, error.column), (3, 10));
+        assert_eq!(error.span, 23..24);
+        assert_eq!(error.message, "unexpected '&', expected ')'");
+        assert_eq!(error.to_string(), "3:10: unexpected '&', expected ')'");
+
+        let errors = parse_errors("(0/1");
+        assert_eq!((errors[0].line, errors[0].column), (1, 5));
+        assert_eq!(errors[0].span, 4..4);
+        assert!(errors[0].message.starts_with("unexpected end of track"));
+
+        assert_eq!(
+            parse_errors("(0/)")[0].message,
+            "unexpected ')', expected a note, rest or group"
+        );
+
+        let errors = parse_errors("(0)x0");
+        assert_eq!(errors[0].column, 5);
+        assert!(errors[0].message.ends_with("a repeat count from 1 to 1024"));
+        assert_eq!(parse_errors("(0)x1025").len(), 1);
+        assert_eq!(parse_errors("(0)x99999999999999999999").len(), 1);
+        assert_eq!(parse_errors("(0)\namp: (0)x0").len(), 1);
+        assert!(parse_errors("(0)x1024").is_empty());
+
+        let errors = parse_errors("(e(3,0))");
+        assert_eq!(errors[0].column, 6);
+        assert!(errors[0].message.contains("a number of steps from 1 to 64"));
+        assert_eq!(parse_errors("(e(3,65))")[0].column, 6);
+        assert!(parse_errors("(e(3,64,99999999999999999999))").is_empty());
+    }
+
+    #[test]
+    fn test_parse_reports_every_broken_line() {
+        let errors = parse_errors("(0/1)\n(0/)\n(2)\n(3]\n(4)");
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+
+        let errors = parse_errors("{0/?\n& 2}\n# (\n(0/)");
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![1, 4]);
+
+        let errors = parse_errors("A = (0)\nA  Chorus");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "unknown section 'Chorus'");
+        assert_eq!((errors[0].line, errors[0].column), (2, 4));
+        assert_eq!(errors[0].span, 11..17);
+    }
+
+    fn lane_values(notation: &str, name: &str, positions: &[f32]) -> Vec<f32> {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse(notation, &scale).unwrap();
+        positions
+            .iter()
+            .map(|&pos| {
+                track.set_playhead(pos);
+                track.lane(name).unwrap().value()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_step_lane() {
+        let notation = "(0)(1)\ncutoff: (0.1/0.5*/_)(0.9)";
+        assert_eq!(
+            lane_values(notation, "cutoff", &[0.0, 0.2, 0.4, 0.6, 0.9]),
+            vec![0.1, 0.5, 0.5, 0.9, 0.9]
+        );
+        // A one-bar lane repeats in every bar.
+        assert_eq!(
+            lane_values("(0)(1)\nres: (0/1)", "res", &[0.3, 0.6, 0.8]),
+            vec![1.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_ramp_lane_follows_playback() {
+        let scale = crate::scale::cmaj();
+        let mut track = Track::parse("(0)\nmix: ramp (0/1)", &scale).unwrap();
+        track.play(0.25);
+        assert!((track.lane("mix").unwrap().value() - 0.5).abs() < 1e-5);
+        track.play(0.75);
+        assert!((track.lane("mix").unwrap().value() - 0.5).abs() < 1e-5);
+        assert_eq!(track.lanes().len(), 1);
+        assert!(track.lane("cutoff").is_none());
+    }
+
+    #[test]
+    fn test_lane_errors() {
+        let errors = parse_errors("(0)\ncutoff: (0)(1)");
+        assert_eq!(errors[0].line, 2);
+        assert!(errors[0].message.contains("2 bars"));
+        assert_eq!(parse_errors("(0)\na: (0)\na: (1)").len(), 1);
+        assert_eq!(parse_errors("(0)\ncutoff: (0/x)")[0].column, 12);
+    }
 }
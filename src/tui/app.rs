@@ -16,18 +16,17 @@ use super::patch::{Patch, PatchSet};
 use super::persist;
 use super::widgets::{
     AdsrWidget, EditWidget, EnvelopeWidget, GridWidget, HelpWidget, PaletteWidget, ProbeWidget,
-    SampleWidget, StatusWidget,
+    SampleWidget, StatusWidget, TrackErrorsWidget,
 };
-#[cfg(feature = "live")]
-use crate::Signal;
+use crate::Groove;
 #[cfg(feature = "live")]
 use crate::live::AudioPlayer;
-use crate::scale::{
-    Scale, amaj, amin, asharpmaj, asharpmin, bmaj, bmin, chromatic, cmaj, cmin, csharpmaj,
-    csharpmin, dmaj, dmin, dsharpmaj, dsharpmin, emaj, emin, fmaj, fmin, fsharpmaj, fsharpmin,
-    gmaj, gmin, gsharpmaj, gsharpmin,
-};
+use crate::scale::{Scale, scale_modes};
 use crate::track::Track;
+use crate::tuning::Tuning;
+use crate::utils::derive_seed;
+#[cfg(feature = "live")]
+use crate::{BusStage, Signal};
 #[cfg(feature = "live")]
 use cpal::traits::StreamTrait;
 use lilt::{Animated, Easing};
@@ -57,7 +56,7 @@ use std::{io, time::Duration};
 
 use tui_input::{Input, InputRequest};
 
-fn scan_wav_files() -> Vec<String> {
+fn scan_files(extension: &str) -> Vec<String> {
     let mut files: Vec<String> = std::fs::read_dir(".")
         .ok()
         .map(|entries| {
@@ -65,7 +64,7 @@ fn scan_wav_files() -> Vec<String> {
                 .filter_map(|e| e.ok())
                 .filter_map(|e| {
                     let path = e.path();
-                    if path.extension().map(|x| x == "wav").unwrap_or(false) {
+                    if path.extension().map(|x| x == extension).unwrap_or(false) {
                         path.file_name()
                             .and_then(|n| n.to_str())
                             .map(|s| s.to_string())
@@ -80,6 +79,10 @@ fn scan_wav_files() -> Vec<String> {
     files
 }
 
+fn scan_wav_files() -> Vec<String> {
+    scan_files("wav")
+}
+
 const BRAND_FONT: &[(&str, [&str; 6])] = &[
     (
         "B",
@@ -153,30 +156,6 @@ fn render_brand(text: &str, gap: usize) -> Vec<String> {
     rows
 }
 
-fn load_wav_samples(path: &str) -> Arc<Vec<f32>> {
-    let Ok(reader) = hound::WavReader::open(path) else {
-        return Arc::new(Vec::new());
-    };
-    let spec = reader.spec();
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Float => reader
-            .into_samples::<f32>()
-            .filter_map(|s| s.ok())
-            .step_by(spec.channels as usize)
-            .collect(),
-        hound::SampleFormat::Int => {
-            let max = (1 << (spec.bits_per_sample - 1)) as f32;
-            reader
-                .into_samples::<i32>()
-                .filter_map(|s| s.ok())
-                .step_by(spec.channels as usize)
-                .map(|s| s as f32 / max)
-                .collect()
-        }
-    };
-    Arc::new(samples)
-}
-
 #[derive(Clone, PartialEq)]
 enum Mode {
     Normal,
@@ -274,6 +253,7 @@ struct App {
     dragging: bool,
     select_start: Option<GridPos>,
     bpm: f32,
+    seed: u64,
     export_input: Input,
     export_loops: usize,
     output_history: VecDeque<f32>,
@@ -288,65 +268,45 @@ struct App {
     bindings: Bindings,
 }
 
-const SCALE_NAMES: &[&str] = &[
-    "Chromatic",
-    "C maj",
-    "C min",
-    "C# maj",
-    "C# min",
-    "D maj",
-    "D min",
-    "D# maj",
-    "D# min",
-    "E maj",
-    "E min",
-    "F maj",
-    "F min",
-    "F# maj",
-    "F# min",
-    "G maj",
-    "G min",
-    "G# maj",
-    "G# min",
-    "A maj",
-    "A min",
-    "A# maj",
-    "A# min",
-    "B maj",
-    "B min",
-];
+const EDO_CHOICES: &[u32] = &[12, 19, 22, 24, 31, 53];
 
-fn scale_from_idx(idx: usize) -> Scale {
-    match idx {
-        0 => chromatic(),
-        1 => cmaj(),
-        2 => cmin(),
-        3 => csharpmaj(),
-        4 => csharpmin(),
-        5 => dmaj(),
-        6 => dmin(),
-        7 => dsharpmaj(),
-        8 => dsharpmin(),
-        9 => emaj(),
-        10 => emin(),
-        11 => fmaj(),
-        12 => fmin(),
-        13 => fsharpmaj(),
-        14 => fsharpmin(),
-        15 => gmaj(),
-        16 => gmin(),
-        17 => gsharpmaj(),
-        18 => gsharpmin(),
-        19 => amaj(),
-        20 => amin(),
-        21 => asharpmaj(),
-        22 => asharpmin(),
-        23 => bmaj(),
-        24 => bmin(),
-        _ => cmin(),
+fn tuning_names() -> Vec<String> {
+    EDO_CHOICES
+        .iter()
+        .map(|n| format!("{}-EDO", n))
+        .chain(scan_files("scl"))
+        .collect()
+}
+
+/// Loads an `N-EDO` name or a `.scl` file, picking up a `.kbm` with the same
+/// stem when one exists.
+fn load_tuning(name: &str) -> Result<Tuning, String> {
+    if let Some(n) = name.strip_suffix("-EDO")
+        && let Ok(divisions) = n.parse()
+    {
+        return Ok(Tuning::edo(divisions));
+    }
+    let tuning = Tuning::load_scl(name)?;
+    let kbm = std::path::Path::new(name).with_extension("kbm");
+    if kbm.exists() {
+        tuning.load_kbm(kbm)
+    } else {
+        Ok(tuning)
     }
 }
 
+fn groove_names() -> Vec<String> {
+    Groove::presets()
+        .iter()
+        .map(|g| g.name().to_string())
+        .chain(scan_files("groove"))
+        .collect()
+}
+
+fn load_groove(name: &str) -> Result<Groove, String> {
+    Groove::preset(name).map_or_else(|| Groove::load(name), Ok)
+}
+
 const SUBPATCH_COLORS: &[Color] = &[
     Color::Rgb(255, 150, 50),
     Color::Rgb(50, 200, 150),
@@ -397,6 +357,7 @@ impl App {
             dragging: false,
             select_start: None,
             bpm: 120.0,
+            seed: 0,
             export_input: Input::new("output.wav".to_string()),
             export_loops: 1,
             output_history: VecDeque::with_capacity(128),
@@ -446,8 +407,8 @@ impl App {
         self.inst_mut().view_center = pos;
     }
 
-    fn scale_idx(&self) -> usize {
-        self.inst().scale_idx
+    fn scale(&self) -> Scale {
+        self.inst().scale
     }
 
     fn track_text(&self) -> &str {
@@ -485,41 +446,59 @@ impl App {
         }
     }
 
+    /// Seed for an instrument's track and patch, so instruments playing the
+    /// same patch don't share random streams.
+    fn instrument_seed(&self, inst_idx: usize) -> u64 {
+        derive_seed(self.seed, inst_idx as u64) as u64
+    }
+
+    /// Parses an instrument's track with its tuning, seed and groove applied.
+    fn instrument_track(&self, inst_idx: usize) -> Option<Track> {
+        let inst = &self.instruments[inst_idx];
+        let mut track = Track::parse(&inst.track_text, &inst.scale).ok()?;
+        track.set_tuning(inst.tuning.clone());
+        track.set_seed(self.instrument_seed(inst_idx));
+        track.set_groove(&inst.groove.clone().with_swing(inst.swing));
+        Some(track)
+    }
+
     fn send_compile(&self, inst_idx: usize) {
         let inst = &self.instruments[inst_idx];
-        let scale = scale_from_idx(inst.scale_idx);
-        let track = Track::parse(&inst.track_text, &scale).ok();
-        let bars = track.as_ref().map(|t| t.bar_count() as f32).unwrap_or(1.0);
+        let track = self.instrument_track(inst_idx);
+        let beats = track.as_ref().map(|t| t.beats()).unwrap_or(4.0);
         let ctx = CompileContext {
-            sample_rate: 44100.0,
+            sample_rate: SAMPLE_RATE as f32,
             bpm: self.bpm,
-            bars,
+            beats,
+            scale: inst.scale,
+            seed: self.instrument_seed(inst_idx),
         };
         let voices = compile_voices(&inst.patches, NUM_VOICES, &ctx);
         let _ = self.cmd_tx.send(AudioCommand::SetVoices {
             idx: inst_idx,
             voices,
-            bars,
+            beats,
             immediate: !self.playing,
         });
     }
 
     fn send_compile_with_track(&self, inst_idx: usize) {
         let inst = &self.instruments[inst_idx];
-        let scale = scale_from_idx(inst.scale_idx);
-        let track = Track::parse(&inst.track_text, &scale).ok();
-        let bars = track.as_ref().map(|t| t.bar_count() as f32).unwrap_or(1.0);
+        let track = self.instrument_track(inst_idx);
+        let beats = track.as_ref().map(|t| t.beats()).unwrap_or(4.0);
         let ctx = CompileContext {
-            sample_rate: 44100.0,
+            sample_rate: SAMPLE_RATE as f32,
             bpm: self.bpm,
-            bars,
+            beats,
+            scale: inst.scale,
+            seed: self.instrument_seed(inst_idx),
         };
         let voices = compile_voices(&inst.patches, NUM_VOICES, &ctx);
         let _ = self.cmd_tx.send(AudioCommand::SetInstrument {
             idx: inst_idx,
             voices,
             track,
-            bars,
+            beats,
             immediate: !self.playing,
         });
     }
@@ -589,6 +568,9 @@ impl App {
                     }
                 }
                 self.active_pitches = frame.active_pitches;
+                if let Some(&id) = frame.faults.first() {
+                    self.message = Some(self.fault_message(id));
+                }
             }
         }
         while let Ok(level) = self.output_rx.try_recv() {
@@ -599,19 +581,109 @@ impl App {
         }
     }
 
+    fn fault_message(&self, id: ModuleId) -> String {
+        let patches = &self.inst().patches;
+        let module = patches.root().module(id).or_else(|| {
+            patches
+                .subpatches()
+                .find_map(|(_, sub)| sub.patch.module(id))
+        });
+        match module {
+            Some(module) => format!("{} produced NaN/Inf and was reset", module.kind.name()),
+            None => "A module produced NaN/Inf and was reset".into(),
+        }
+    }
+
     fn reparse_track(&mut self) {
-        let scale = scale_from_idx(self.scale_idx());
+        let scale = self.scale();
         match Track::parse(self.track_text(), &scale) {
-            Ok(_) => {
+            Ok(track) => {
+                self.inst_mut().track_errors.clear();
+                self.inst_mut().meters = track.meters().to_vec();
                 self.send_compile_with_track(self.current_instrument);
                 self.message = Some("Track updated".into());
             }
+            Err(errors) => {
+                self.message = Some(match errors.as_slice() {
+                    [] => "Parse error".into(),
+                    [first] => format!("Parse error at {}", first),
+                    [first, rest @ ..] => {
+                        format!("Parse error at {} (+{} more)", first, rest.len())
+                    }
+                });
+                self.inst_mut().track_errors = errors;
+            }
+        }
+    }
+
+    fn cycle_scale_mode(&mut self, direction: isize) {
+        let modes: Vec<_> = scale_modes().collect();
+        let current = self.scale().mode_name();
+        let idx = modes
+            .iter()
+            .position(|m| Some(*m) == current)
+            .map(|i| (i as isize + direction).rem_euclid(modes.len() as isize))
+            .unwrap_or(0);
+        if let Some(mut scale) = Scale::mode(modes[idx as usize]) {
+            scale.root(self.scale().pitch_class());
+            self.inst_mut().scale = scale;
+            self.reparse_track();
+        }
+    }
+
+    fn shift_scale_root(&mut self, direction: i32) {
+        let root = self.scale().pitch_class() + direction;
+        self.inst_mut().scale.root(root);
+        self.reparse_track();
+    }
+
+    fn cycle_tuning(&mut self, direction: isize) {
+        let names = tuning_names();
+        let current = names
+            .iter()
+            .position(|n| *n == self.inst().tuning_name)
+            .unwrap_or(0) as isize;
+        let name = &names[(current + direction).rem_euclid(names.len() as isize) as usize];
+        match load_tuning(name) {
+            Ok(tuning) => {
+                let inst = self.inst_mut();
+                inst.tuning_name = name.clone();
+                inst.tuning = tuning;
+                self.reparse_track();
+                self.dirty = true;
+            }
             Err(e) => {
-                self.message = Some(format!("Parse error: {}", e));
+                self.message = Some(format!("Tuning {}: {}", name, e));
             }
         }
     }
 
+    fn cycle_groove(&mut self, direction: isize) {
+        let names = groove_names();
+        let current = names
+            .iter()
+            .position(|n| n == self.inst().groove.name())
+            .unwrap_or(0) as isize;
+        let name = &names[(current + direction).rem_euclid(names.len() as isize) as usize];
+        match load_groove(name) {
+            Ok(groove) => {
+                self.inst_mut().groove = groove;
+                self.reparse_track();
+                self.dirty = true;
+            }
+            Err(e) => {
+                self.message = Some(format!("Groove {}: {}", name, e));
+            }
+        }
+    }
+
+    fn adjust_swing(&mut self, delta: f32) {
+        let inst = self.inst_mut();
+        inst.swing = (inst.swing + delta).clamp(50.0, 75.0);
+        self.reparse_track();
+        self.dirty = true;
+    }
+
     fn snapshot(&mut self) {
         self.inst_mut().snapshot();
     }
@@ -1230,6 +1302,14 @@ impl App {
             Action::ToggleMeters => {
                 self.show_meters = !self.show_meters;
             }
+            Action::RerollSeed => {
+                self.seed = fastrand::u64(..);
+                for idx in 0..self.instruments.len() {
+                    self.send_compile_with_track(idx);
+                }
+                self.dirty = true;
+                self.message = Some(format!("Seed {:016x}", self.seed));
+            }
             Action::Instrument(idx) => {
                 if idx < self.instruments.len() {
                     self.switch_instrument(idx);
@@ -2155,6 +2235,10 @@ impl App {
                         && param_idx == 0
                     {
                         self.cycle_sample_file(module_id, false);
+                    } else if matches!(module.kind, ModuleKind::Standard(StandardModule::Lane))
+                        && param_idx == 0
+                    {
+                        self.cycle_lane(module_id, false);
                     } else {
                         let step = self.step_value();
                         if let Some(m) = self.patch_mut().module_mut(module_id) {
@@ -2202,6 +2286,10 @@ impl App {
                         && param_idx == 0
                     {
                         self.cycle_sample_file(module_id, true);
+                    } else if matches!(module.kind, ModuleKind::Standard(StandardModule::Lane))
+                        && param_idx == 0
+                    {
+                        self.cycle_lane(module_id, true);
                     } else {
                         let step = self.step_value();
                         if let Some(m) = self.patch_mut().module_mut(module_id) {
@@ -2512,8 +2600,8 @@ impl App {
         let new_name = files.get(new_idx).cloned().unwrap_or_default();
         let new_samples = files
             .get(new_idx)
-            .map(|p| load_wav_samples(p))
-            .unwrap_or_else(|| Arc::new(Vec::new()));
+            .and_then(|p| persist::load_wav_samples(p, SAMPLE_RATE))
+            .unwrap_or_default();
 
         if let Some(m) = self.patch_mut().module_mut(module_id)
             && let ModuleParams::Sample {
@@ -2529,6 +2617,29 @@ impl App {
         }
     }
 
+    /// Steps a Lane module through the lanes named in the current track.
+    fn cycle_lane(&mut self, module_id: ModuleId, forward: bool) {
+        let scale = self.scale();
+        let names: Vec<String> = Track::parse(self.track_text(), &scale)
+            .map(|t| t.lanes().iter().map(|l| l.name().to_string()).collect())
+            .unwrap_or_default();
+        if names.is_empty() {
+            self.message = Some("Track has no lanes".into());
+            return;
+        }
+
+        if let Some(m) = self.patch_mut().module_mut(module_id)
+            && let ModuleParams::Lane { name } = &mut m.params
+        {
+            let new_idx = match names.iter().position(|n| n == name) {
+                Some(idx) if forward => (idx + 1) % names.len(),
+                Some(idx) => (idx + names.len() - 1) % names.len(),
+                None => 0,
+            };
+            *name = names[new_idx].clone();
+        }
+    }
+
     fn cycle_delay_tap_source(&mut self, tap_id: ModuleId, forward: bool) {
         let delays: Vec<ModuleId> = self
             .patch()
@@ -2689,40 +2800,42 @@ impl App {
     }
 
     fn export_to_wav(&mut self) {
-        use crate::Signal;
+        use crate::{BusStage, Signal};
 
-        let sample_rate = 44100usize;
-        let scale = scale_from_idx(self.scale_idx());
-        let track = Track::parse(self.track_text(), &scale).ok();
-        let bars = track.as_ref().map(|t| t.bar_count() as f32).unwrap_or(1.0);
+        let sample_rate = SAMPLE_RATE as usize;
+        let scale = self.scale();
+        let track = self.instrument_track(self.current_instrument);
+        let beats = track.as_ref().map(|t| t.beats()).unwrap_or(4.0);
         let bpm = self.bpm;
 
         let seconds_per_beat = 60.0 / bpm;
-        let beats_per_bar = 4.0;
-        let duration = seconds_per_beat * beats_per_bar * bars * self.export_loops as f32;
+        let duration = seconds_per_beat * beats * self.export_loops as f32;
         let total_samples = (duration * sample_rate as f32) as usize;
 
         let mut compiled = CompiledPatch::default();
         let ctx = CompileContext {
             sample_rate: sample_rate as f32,
             bpm,
-            bars,
+            beats,
+            scale,
+            seed: self.instrument_seed(self.current_instrument),
         };
         compile_patch(&mut compiled, self.patches(), NUM_VOICES, &ctx, true);
 
         let mut track_state = TrackState::new(NUM_VOICES);
-        track_state.clock.bpm(bpm).bars(bars);
+        track_state.clock.bpm(bpm).beats(beats);
         track_state.set_track(track);
+        compiled.resolve_lanes(track_state.lanes());
 
         let mut signal = Signal::new(sample_rate);
+        signal.bus.stage(BusStage::Limiter);
         let mut samples = Vec::with_capacity(total_samples);
 
         for _ in 0..total_samples {
             track_state.update(&mut signal);
-            let sample = compiled
-                .process(&mut signal, &track_state, 0)
-                .clamp(-1.0, 1.0);
-            samples.push(sample);
+            let sample = compiled.process(&mut signal, &track_state, 0);
+            signal.add_sample(sample);
+            samples.push(signal.mix());
             signal.advance();
         }
 
@@ -2763,7 +2876,7 @@ impl App {
         } else {
             Some(track_str)
         };
-        let scale = scale_from_idx(self.scale_idx());
+        let scale = self.scale();
         let bars = Track::parse(track_str, &scale)
             .ok()
             .map(|t| t.bar_count() as f32)
@@ -2774,8 +2887,12 @@ impl App {
             self.patches(),
             self.bpm,
             bars,
-            self.scale_idx(),
+            self.scale(),
             track,
+            self.seed,
+            Some(&self.inst().tuning_name),
+            self.inst().swing,
+            Some(self.inst().groove.name()),
         ) {
             Ok(()) => {
                 self.file_path = Some(path.clone());
@@ -2789,19 +2906,54 @@ impl App {
     }
 
     fn load_from_file(&mut self, path: PathBuf) {
-        match persist::load_patchset(&path) {
+        match persist::load_patchset(&path, SAMPLE_RATE) {
             Ok(result) => {
                 {
                     let inst = self.inst_mut();
                     inst.patches = result.patches;
-                    inst.scale_idx = result.scale_idx;
+                    inst.scale = result.scale;
                     if let Some(track_text) = result.track {
                         inst.track_text = track_text;
                     }
+                    match Track::parse(&inst.track_text, &inst.scale) {
+                        Ok(track) => {
+                            inst.track_errors.clear();
+                            inst.meters = track.meters().to_vec();
+                        }
+                        Err(errors) => inst.track_errors = errors,
+                    }
                 }
+                let tuning_name = result.tuning.unwrap_or_else(|| "12-EDO".to_string());
+                let tuning_error = match load_tuning(&tuning_name) {
+                    Ok(tuning) => {
+                        let inst = self.inst_mut();
+                        inst.tuning_name = tuning_name;
+                        inst.tuning = tuning;
+                        None
+                    }
+                    Err(e) => {
+                        let inst = self.inst_mut();
+                        inst.tuning_name = "12-EDO".to_string();
+                        inst.tuning = Tuning::default();
+                        Some(format!("Tuning {}: {}", tuning_name, e))
+                    }
+                };
+                let groove_name = result.groove.unwrap_or_else(|| "Straight".to_string());
+                let groove_error = match load_groove(&groove_name) {
+                    Ok(groove) => {
+                        self.inst_mut().groove = groove;
+                        None
+                    }
+                    Err(e) => {
+                        self.inst_mut().groove = Groove::straight();
+                        Some(format!("Groove {}: {}", groove_name, e))
+                    }
+                };
+                self.inst_mut().swing = result.swing;
 
                 self.file_path = Some(path.clone());
                 self.bpm = result.bpm;
+                self.seed = result.seed;
                 let _ = self.cmd_tx.send(AudioCommand::SetBpm(self.bpm));
 
                 self.snapshot();
@@ -2826,6 +2978,9 @@ impl App {
                         more
                     ));
                 }
+                if let Some(e) = tuning_error.or(groove_error) {
+                    self.message = Some(e);
+                }
             }
             Err(e) => {
                 self.message = Some(format!("Load failed: {}", e));
@@ -3269,11 +3424,11 @@ impl App {
                 self.mode = Mode::Normal;
             }
             Action::Down => {
-                let new_idx = (param_idx + 1) % 3;
+                let new_idx = (param_idx + 1) % 6;
                 self.mode = Mode::TrackSettings { param_idx: new_idx };
             }
             Action::Up => {
-                let new_idx = if param_idx == 0 { 2 } else { param_idx - 1 };
+                let new_idx = if param_idx == 0 { 5 } else { param_idx - 1 };
                 self.mode = Mode::TrackSettings { param_idx: new_idx };
             }
             Action::ValueUp => match param_idx {
@@ -3281,17 +3436,16 @@ impl App {
                     self.bpm = (self.bpm + 5.0).min(300.0);
                     let _ = self.cmd_tx.send(AudioCommand::SetBpm(self.bpm));
                 }
-                1 => {
-                    let new_idx = (self.scale_idx() + 1) % SCALE_NAMES.len();
-                    self.inst_mut().scale_idx = new_idx;
-                    self.reparse_track();
-                }
+                1 => self.cycle_scale_mode(1),
                 2 => {
                     let _ = self.cmd_tx.send(AudioCommand::SetProbeVoice(
                         (self.probe_voice + 1) % NUM_VOICES,
                     ));
                     self.probe_voice = (self.probe_voice + 1) % NUM_VOICES;
                 }
+                3 => self.cycle_tuning(1),
+                4 => self.adjust_swing(1.0),
+                5 => self.cycle_groove(1),
                 _ => {}
             },
             Action::ValueDown => match param_idx {
@@ -3299,16 +3453,7 @@ impl App {
                     self.bpm = (self.bpm - 5.0).max(20.0);
                     let _ = self.cmd_tx.send(AudioCommand::SetBpm(self.bpm));
                 }
-                1 => {
-                    let cur = self.scale_idx();
-                    let new_idx = if cur == 0 {
-                        SCALE_NAMES.len() - 1
-                    } else {
-                        cur - 1
-                    };
-                    self.inst_mut().scale_idx = new_idx;
-                    self.reparse_track();
-                }
+                1 => self.cycle_scale_mode(-1),
                 2 => {
                     let v = if self.probe_voice == 0 {
                         NUM_VOICES - 1
@@ -3318,20 +3463,29 @@ impl App {
                     let _ = self.cmd_tx.send(AudioCommand::SetProbeVoice(v));
                     self.probe_voice = v;
                 }
+                3 => self.cycle_tuning(-1),
+                4 => self.adjust_swing(-1.0),
+                5 => self.cycle_groove(-1),
                 _ => {}
             },
-            Action::ValueUpFast => {
-                if param_idx == 0 {
+            Action::ValueUpFast => match param_idx {
+                0 => {
                     self.bpm = (self.bpm + 20.0).min(300.0);
                     let _ = self.cmd_tx.send(AudioCommand::SetBpm(self.bpm));
                 }
-            }
-            Action::ValueDownFast => {
-                if param_idx == 0 {
+                1 => self.shift_scale_root(1),
+                4 => self.adjust_swing(5.0),
+                _ => {}
+            },
+            Action::ValueDownFast => match param_idx {
+                0 => {
                     self.bpm = (self.bpm - 20.0).max(20.0);
                     let _ = self.cmd_tx.send(AudioCommand::SetBpm(self.bpm));
                 }
-            }
+                1 => self.shift_scale_root(-1),
+                4 => self.adjust_swing(-5.0),
+                _ => {}
+            },
             _ => {}
         }
     }
@@ -3478,6 +3632,33 @@ impl App {
             f.render_widget(grid_widget, grid_area);
         }
 
+        let track_errors = &self.inst().track_errors;
+        if !track_errors.is_empty() {
+            let height = (TrackErrorsWidget::height(track_errors) + 2).min(grid_area.height / 2);
+            let errors_area = Rect::new(
+                grid_area.x,
+                grid_area.y + grid_area.height - height,
+                grid_area.width,
+                height,
+            );
+            f.render_widget(Clear, errors_area);
+            let errors_block = Block::default()
+                .title(" Track errors ")
+                .borders(Borders::TOP)
+                .border_style(Style::default().fg(Color::Red));
+            let inner = errors_block.inner(errors_area);
+            f.render_widget(errors_block, errors_area);
+            f.render_widget(
+                TrackErrorsWidget::new(self.track_text(), track_errors),
+                Rect::new(
+                    inner.x + 1,
+                    inner.y,
+                    inner.width.saturating_sub(2),
+                    inner.height,
+                ),
+            );
+        }
+
         let help_block = Block::default()
             .borders(Borders::LEFT)
             .border_style(Style::default().fg(Color::Rgb(60, 60, 60)));
@@ -3566,7 +3747,7 @@ impl App {
         let note_style = Style::default().fg(Color::Yellow);
         let note_active_style = Style::default().fg(Color::Black).bg(Color::Yellow);
 
-        let scale = scale_from_idx(self.scale_idx());
+        let scale = self.scale();
         let mut scale_pitches: Vec<u8> = (-49..49i32)
             .map(|deg| scale.note(deg))
             .filter(|&p| (12..=120).contains(&p))
@@ -3645,7 +3826,8 @@ impl App {
         let history: Vec<f32> = self.output_history.iter().copied().collect();
         let mut status = StatusWidget::new(self.cursor(), mode_str)
             .playing(self.playing)
-            .output_history(&history);
+            .output_history(&history)
+            .tempo(self.bpm, &self.inst().meters);
         if let Some(ref msg) = self.message {
             status = status.message(msg);
         }
@@ -3893,7 +4075,7 @@ impl App {
                 sample_area.width.saturating_sub(2),
                 sample_area.height.saturating_sub(2),
             );
-            let sample_widget = SampleWidget::new(samples, zoom, offset);
+            let sample_widget = SampleWidget::new(samples.left(), zoom, offset);
             f.render_widget(sample_widget, inner);
         }
 
@@ -3993,7 +4175,7 @@ impl App {
 
         if let Mode::TrackSettings { param_idx } = self.mode {
             let width = 30u16;
-            let height = 7u16;
+            let height = 10u16;
             let x = (f.area().width.saturating_sub(width)) / 2;
             let y = (f.area().height.saturating_sub(height)) / 2;
             let area = Rect::new(x, y, width, height);
@@ -4013,9 +4195,15 @@ impl App {
             let bpm_label = "BPM: ";
             let bpm_value = format!("{:.0}", self.bpm);
             let scale_label = "Scale: ";
-            let scale_value = SCALE_NAMES[self.scale_idx()];
+            let scale_value = self.scale().to_string();
             let voice_label = "Probe Voice: ";
             let voice_value = format!("{}", self.probe_voice + 1);
+            let tuning_label = "Tuning: ";
+            let tuning_value = self.inst().tuning_name.clone();
+            let swing_label = "Swing: ";
+            let swing_value = format!("{:.0}%", self.inst().swing);
+            let groove_label = "Groove: ";
+            let groove_value = self.inst().groove.name().to_string();
 
             let bpm_style = if param_idx == 0 {
                 selected_style
@@ -4032,6 +4220,21 @@ impl App {
             } else {
                 value_style
             };
+            let tuning_style = if param_idx == 3 {
+                selected_style
+            } else {
+                value_style
+            };
+            let swing_style = if param_idx == 4 {
+                selected_style
+            } else {
+                value_style
+            };
+            let groove_style = if param_idx == 5 {
+                selected_style
+            } else {
+                value_style
+            };
 
             let inner_x = area.x + 2;
             let inner_y = area.y + 2;
@@ -4082,11 +4285,61 @@ impl App {
                     buf[(voice_x + ix, row3)].set_char(c).set_style(voice_style);
                 }
             }
+
+            let row4 = inner_y + 3;
+            for (i, c) in tuning_label.chars().enumerate() {
+                let ix = i as u16;
+                if inner_x + ix < area.x + area.width - 1 {
+                    buf[(inner_x + ix, row4)].set_char(c).set_style(label_style);
+                }
+            }
+            let tuning_x = inner_x + tuning_label.len() as u16;
+            for (i, c) in tuning_value.chars().enumerate() {
+                let ix = i as u16;
+                if tuning_x + ix < area.x + area.width - 1 {
+                    buf[(tuning_x + ix, row4)]
+                        .set_char(c)
+                        .set_style(tuning_style);
+                }
+            }
+
+            let row5 = inner_y + 4;
+            for (i, c) in swing_label.chars().enumerate() {
+                let ix = i as u16;
+                if inner_x + ix < area.x + area.width - 1 {
+                    buf[(inner_x + ix, row5)].set_char(c).set_style(label_style);
+                }
+            }
+            let swing_x = inner_x + swing_label.len() as u16;
+            for (i, c) in swing_value.chars().enumerate() {
+                let ix = i as u16;
+                if swing_x + ix < area.x + area.width - 1 {
+                    buf[(swing_x + ix, row5)].set_char(c).set_style(swing_style);
+                }
+            }
+
+            let row6 = inner_y + 5;
+            for (i, c) in groove_label.chars().enumerate() {
+                let ix = i as u16;
+                if inner_x + ix < area.x + area.width - 1 {
+                    buf[(inner_x + ix, row6)].set_char(c).set_style(label_style);
+                }
+            }
+            let groove_x = inner_x + groove_label.len() as u16;
+            for (i, c) in groove_value.chars().enumerate() {
+                let ix = i as u16;
+                if groove_x + ix < area.x + area.width - 1 {
+                    buf[(groove_x + ix, row6)]
+                        .set_char(c)
+                        .set_style(groove_style);
+                }
+            }
         }
     }
 }
 
 const NUM_VOICES: usize = 6;
+const SAMPLE_RATE: u32 = 44100;
 
 pub fn run() -> Result<(), Box<dyn std::error::Error>> {
     let file_arg = std::env::args().nth(1).map(PathBuf::from);
@@ -4109,6 +4362,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
 
             let mut engine = AudioEngine::new(NUM_VOICES, sample_rate, cmd_rx, meter_tx);
             let mut signal = Signal::new(sample_rate as usize);
+            signal.bus.stage(BusStage::Limiter);
             let channels = player.config.channels as usize;
 
             let mut output_counter = 0usize;
@@ -4144,7 +4398,7 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
                             fade_gain = (fade_gain - fade_step).max(0.0);
                         }
 
-                        let sample = assert_no_alloc(|| engine.process(&mut signal).clamp(-1., 1.));
+                        let sample = assert_no_alloc(|| engine.process(&mut signal));
                         let sample = sample * fade_gain;
 
                         for channel_sample in frame.iter_mut() {
@@ -41,6 +41,7 @@ pub enum Action {
     EditSubpatch,
     ExitSubpatch,
     ToggleMeters,
+    RerollSeed,
     OpenPalette,
     Export,
     TypeValue,
@@ -86,6 +87,7 @@ impl Action {
             Action::CycleStep => "step size",
             Action::EditSubpatch | Action::ExitSubpatch => "sub",
             Action::ToggleMeters => "meters",
+            Action::RerollSeed => "reroll seed",
             Action::OpenPalette => "new module",
             Action::Export => "export wav",
             Action::Instrument(_) => "instrument",
@@ -266,6 +268,13 @@ pub fn normal_bindings() -> &'static [Binding] {
             group: None,
             section: 3,
         },
+        Binding {
+            key: KeyCode::Char('R'),
+            action: Action::RerollSeed,
+            hint: None,
+            group: None,
+            section: 3,
+        },
         Binding {
             key: KeyCode::Char('p'),
             action: Action::EditSubpatch,
@@ -135,6 +135,7 @@ pub fn parse_action(s: &str) -> Option<Action> {
         "edit_subpatch" => Some(Action::EditSubpatch),
         "exit_subpatch" => Some(Action::ExitSubpatch),
         "toggle_meters" => Some(Action::ToggleMeters),
+        "reroll_seed" => Some(Action::RerollSeed),
         "open_palette" => Some(Action::OpenPalette),
         "export" => Some(Action::Export),
         "type_value" => Some(Action::TypeValue),
@@ -1,7 +1,10 @@
 use super::grid::{Cell, GridPos};
-use super::module::{Module, ModuleId, ModuleKind, StandardModule, SubPatchId, SubpatchModule};
+use super::module::{
+    Module, ModuleId, ModuleKind, SampleChannel, StandardModule, SubPatchId, SubpatchModule,
+};
 use super::patch::{Patch, PatchSet};
 use crate::allpass::AllpassFilter;
+use crate::bitcrusher::Bitcrusher;
 use crate::clock::Clock;
 use crate::comb::CombFilter;
 use crate::delay::Delay;
@@ -10,10 +13,16 @@ use crate::envelopes::{ADSR, Envelope, EnvelopePoint, PointType};
 use crate::filters::{HighpassFilter, LowpassFilter};
 use crate::flanger::Flanger;
 use crate::gate_ramp::GateRamp;
+use crate::hilbert::{FrequencyShifter, RingModulator};
 use crate::oscillators::Osc;
+use crate::pitch_shift::PitchShifter;
 use crate::ramp::Ramp;
 use crate::reverb::Reverb;
-use crate::track::{NoteEvent, Track};
+use crate::sample::SampleBuffer;
+use crate::scale::{Scale, cmaj};
+use crate::track::{Lane, NoteEvent, Track};
+use crate::utils::{derive_seed, flush_denormal, next_random};
+use crate::vocoder::Vocoder;
 use std::collections::{HashMap, VecDeque};
 
 pub type MeterSender = flume::Sender<MeterFrame>;
@@ -41,13 +50,13 @@ pub enum AudioCommand {
         idx: usize,
         voices: Vec<CompiledVoice>,
         track: Option<Track>,
-        bars: f32,
+        beats: f32,
         immediate: bool,
     },
     SetVoices {
         idx: usize,
         voices: Vec<CompiledVoice>,
-        bars: f32,
+        beats: f32,
         immediate: bool,
     },
     SetProbeVoice(usize),
@@ -58,12 +67,14 @@ pub struct MeterFrame {
     pub ports: Vec<(ModuleId, Vec<f32>)>,
     pub probes: Vec<(ModuleId, Vec<f32>)>,
     pub active_pitches: Vec<u8>,
+    pub faults: Vec<ModuleId>,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
 struct Voice {
     pitch: u8,
     degree: i32,
+    velocity: f32,
     freq: f32,
     gate: f32,
     age: usize,
@@ -79,7 +90,7 @@ pub struct TrackState {
 impl TrackState {
     pub fn new(num_voices: usize) -> Self {
         let mut clock = Clock::default();
-        clock.bpm(120.0).bars(1.0);
+        clock.bpm(120.0).beats(4.0);
         Self {
             track: None,
             clock,
@@ -108,12 +119,16 @@ impl TrackState {
         let Some(track) = &mut self.track else { return };
 
         let phase = self.clock.output(signal);
-        let events = track.play(phase);
+        track.play(phase);
 
-        for event in events {
+        for &event in track.events() {
             match event {
-                NoteEvent::Press { pitch, degree } => {
-                    let freq = 440.0 * 2.0f32.powf((pitch as f32 - 69.0) / 12.0);
+                NoteEvent::Press {
+                    pitch,
+                    degree,
+                    velocity,
+                } => {
+                    let freq = track.frequency(pitch);
                     self.age_counter += 1;
 
                     let idx = self
@@ -145,6 +160,7 @@ impl TrackState {
                         let v = &mut self.voices[i];
                         v.pitch = pitch;
                         v.degree = degree;
+                        v.velocity = velocity as f32 / 127.0;
                         v.freq = freq;
                         v.gate = 1.0;
                         v.age = self.age_counter;
@@ -163,9 +179,13 @@ impl TrackState {
         }
     }
 
-    pub fn voice(&self, idx: usize) -> (f32, f32, i32) {
+    pub fn voice(&self, idx: usize) -> (f32, f32, i32, f32) {
         let v = &self.voices[idx];
-        (v.freq, v.gate, v.degree)
+        (v.freq, v.gate, v.degree, v.velocity)
+    }
+
+    pub fn lanes(&self) -> &[Lane] {
+        self.track.as_ref().map_or(&[], |track| track.lanes())
     }
 
     pub fn active_pitches(&self) -> Vec<u8> {
@@ -181,7 +201,16 @@ enum NodeKind {
     Freq,
     Gate,
     Degree,
-    DegreeGate { target: i32 },
+    Velocity,
+    // Index into the track's lanes, resolved by name when the voices or
+    // the track change.
+    Lane {
+        name: String,
+        index: Option<usize>,
+    },
+    DegreeGate {
+        target: i32,
+    },
     Oscillator(Osc),
     Rise(GateRamp),
     Fall(GateRamp),
@@ -193,20 +222,34 @@ enum NodeKind {
     Comb(CombFilter),
     Allpass(AllpassFilter),
     Delay(Delay),
-    DelayTap { delay_node: usize, gain: f32 },
+    DelayTap {
+        delay_node: usize,
+        gain: f32,
+    },
     Reverb(Reverb),
     Distortion(Distortion),
+    Crush(Bitcrusher),
+    Ring(RingModulator),
+    FreqShift(FrequencyShifter),
+    Vocoder(Vocoder),
+    PitchShift(PitchShifter, Option<Scale>),
     Flanger(Flanger),
     Mul,
     Add,
     Gt,
     Lt,
     Switch,
-    Rng { last_gate: f32, value: f32 },
-    Sample { samples: std::sync::Arc<Vec<f32>> },
+    Rng {
+        last_gate: f32,
+        value: f32,
+        seed: u32,
+    },
+    Sample(std::sync::Arc<SampleBuffer>, SampleChannel),
     Probe,
     Pass,
-    Output { gain: f32 },
+    Output {
+        gain: f32,
+    },
 }
 
 impl NodeKind {
@@ -249,31 +292,53 @@ impl NodeKind {
             (NodeKind::Flanger(new), NodeKind::Flanger(old)) => {
                 new.copy_state_from(old);
             }
+            (NodeKind::Distortion(new), NodeKind::Distortion(old)) => {
+                new.copy_state_from(old);
+            }
+            (NodeKind::Crush(new), NodeKind::Crush(old)) => {
+                new.copy_state_from(old);
+            }
+            (NodeKind::Ring(new), NodeKind::Ring(old)) => {
+                new.copy_state_from(old);
+            }
+            (NodeKind::FreqShift(new), NodeKind::FreqShift(old)) => {
+                new.copy_state_from(old);
+            }
+            (NodeKind::Vocoder(new), NodeKind::Vocoder(old)) => {
+                new.copy_state_from(old);
+            }
+            (NodeKind::PitchShift(new, _), NodeKind::PitchShift(old, _)) => {
+                new.copy_state_from(old);
+            }
             (
                 NodeKind::Rng {
                     last_gate: new_lg,
                     value: new_v,
+                    seed: new_s,
                 },
                 NodeKind::Rng {
                     last_gate: old_lg,
                     value: old_v,
+                    seed: old_s,
                 },
             ) => {
                 *new_lg = *old_lg;
                 *new_v = *old_v;
+                *new_s = *old_s;
             }
             (NodeKind::Freq, NodeKind::Freq)
             | (NodeKind::Gate, NodeKind::Gate)
             | (NodeKind::Degree, NodeKind::Degree)
+            | (NodeKind::Velocity, NodeKind::Velocity)
+            | (NodeKind::Lane { .. }, NodeKind::Lane { .. })
             | (NodeKind::DegreeGate { .. }, NodeKind::DegreeGate { .. })
             | (NodeKind::DelayTap { .. }, NodeKind::DelayTap { .. })
-            | (NodeKind::Distortion(_), NodeKind::Distortion(_))
             | (NodeKind::Mul, NodeKind::Mul)
             | (NodeKind::Add, NodeKind::Add)
             | (NodeKind::Gt, NodeKind::Gt)
             | (NodeKind::Lt, NodeKind::Lt)
             | (NodeKind::Switch, NodeKind::Switch)
-            | (NodeKind::Sample { .. }, NodeKind::Sample { .. })
+            | (NodeKind::Sample(..), NodeKind::Sample(..))
             | (NodeKind::Probe, NodeKind::Probe)
             | (NodeKind::Pass, NodeKind::Pass)
             | (NodeKind::Output { .. }, NodeKind::Output { .. }) => {}
@@ -282,6 +347,48 @@ impl NodeKind {
     }
 }
 
+impl NodeKind {
+    fn clear_state(&mut self) {
+        match self {
+            NodeKind::Rise(ramp) | NodeKind::Fall(ramp) => ramp.reset(),
+            NodeKind::Adsr(adsr) => adsr.reset(),
+            NodeKind::Lpf(filter) => filter.reset(),
+            NodeKind::Hpf(filter) => filter.reset(),
+            NodeKind::Comb(comb) => comb.reset(),
+            NodeKind::Allpass(allpass) => allpass.reset(),
+            NodeKind::Delay(delay) => delay.reset(),
+            NodeKind::Reverb(reverb) => reverb.reset(),
+            NodeKind::Distortion(distortion) => distortion.reset(),
+            NodeKind::Crush(crush) => crush.reset(),
+            NodeKind::FreqShift(shifter) => shifter.reset(),
+            NodeKind::Vocoder(vocoder) => vocoder.reset(),
+            NodeKind::PitchShift(shifter, _) => shifter.reset(),
+            NodeKind::Flanger(flanger) => flanger.reset(),
+            NodeKind::Rng { value, .. } => *value = 0.0,
+            NodeKind::Oscillator(osc) => osc.reset(),
+            NodeKind::Ramp(ramp) => ramp.reset(),
+            NodeKind::Envelope(env) => env.reset(),
+            NodeKind::Ring(ring) => ring.reset(),
+            NodeKind::Freq
+            | NodeKind::Gate
+            | NodeKind::Degree
+            | NodeKind::Velocity
+            | NodeKind::Lane { .. }
+            | NodeKind::DegreeGate { .. }
+            | NodeKind::DelayTap { .. }
+            | NodeKind::Mul
+            | NodeKind::Add
+            | NodeKind::Gt
+            | NodeKind::Lt
+            | NodeKind::Switch
+            | NodeKind::Sample(..)
+            | NodeKind::Probe
+            | NodeKind::Pass
+            | NodeKind::Output { .. } => {}
+        }
+    }
+}
+
 struct AudioNode {
     kind: NodeKind,
     module_id: ModuleId,
@@ -296,9 +403,19 @@ pub struct CompiledVoice {
     execution_order: Vec<usize>,
     output_node: Option<usize>,
     last_gate: f32,
+    fault: Option<ModuleId>,
 }
 
 impl CompiledVoice {
+    /// Points each Lane node at the track lane with its name.
+    pub fn resolve_lanes(&mut self, lanes: &[Lane]) {
+        for node in &mut self.nodes {
+            if let NodeKind::Lane { name, index } = &mut node.kind {
+                *index = lanes.iter().position(|lane| lane.name() == name);
+            }
+        }
+    }
+
     fn inherit_state_from(&mut self, old: &CompiledVoice) {
         self.last_gate = old.last_gate;
         for new_node in &mut self.nodes {
@@ -317,6 +434,8 @@ impl CompiledVoice {
                 NodeKind::Freq
                 | NodeKind::Gate
                 | NodeKind::Degree
+                | NodeKind::Velocity
+                | NodeKind::Lane { .. }
                 | NodeKind::DegreeGate { .. }
                 | NodeKind::Oscillator(_)
                 | NodeKind::Ramp(_)
@@ -329,6 +448,11 @@ impl CompiledVoice {
                 | NodeKind::DelayTap { .. }
                 | NodeKind::Reverb(_)
                 | NodeKind::Distortion(_)
+                | NodeKind::Crush(_)
+                | NodeKind::Ring(_)
+                | NodeKind::FreqShift(_)
+                | NodeKind::Vocoder(_)
+                | NodeKind::PitchShift(..)
                 | NodeKind::Flanger(_)
                 | NodeKind::Mul
                 | NodeKind::Add
@@ -336,7 +460,7 @@ impl CompiledVoice {
                 | NodeKind::Lt
                 | NodeKind::Switch
                 | NodeKind::Rng { .. }
-                | NodeKind::Sample { .. }
+                | NodeKind::Sample(..)
                 | NodeKind::Probe
                 | NodeKind::Pass
                 | NodeKind::Output { .. } => {}
@@ -346,7 +470,15 @@ impl CompiledVoice {
         }
     }
 
-    fn process(&mut self, signal: &mut crate::Signal, freq: f32, gate: f32, degree: i32) -> f32 {
+    fn process(
+        &mut self,
+        signal: &mut crate::Signal,
+        freq: f32,
+        gate: f32,
+        degree: i32,
+        velocity: f32,
+        lanes: &[Lane],
+    ) -> f32 {
         if gate > 0.5 && self.last_gate < 0.5 {
             self.reset();
         }
@@ -389,6 +521,10 @@ impl CompiledVoice {
                 NodeKind::Freq => freq,
                 NodeKind::Gate => gate,
                 NodeKind::Degree => degree as f32,
+                NodeKind::Velocity => velocity,
+                NodeKind::Lane { index, .. } => index
+                    .and_then(|idx| lanes.get(idx))
+                    .map_or(0.0, Lane::value),
                 NodeKind::DegreeGate { target } => {
                     if gate > 0.5 && degree == *target {
                         1.0
@@ -426,6 +562,48 @@ impl CompiledVoice {
                 NodeKind::DelayTap { .. } => delay_tap_value.unwrap_or(0.0),
                 NodeKind::Reverb(reverb) => reverb.output(in0),
                 NodeKind::Distortion(dist) => dist.output(in0),
+                NodeKind::Crush(crush) => {
+                    let port = |i: usize| node.input_values.get(i).copied().unwrap_or(0.0);
+                    crush
+                        .bits(in1)
+                        .rate(in2)
+                        .jitter(port(3))
+                        .drive(port(4))
+                        .mix(port(5))
+                        .output(in0, signal)
+                }
+                NodeKind::Ring(ring) => ring.freq(in1).mix(in2).output(in0, signal),
+                NodeKind::FreqShift(shifter) => {
+                    let (shifted, _) = shifter.shift(in1).output(in0, signal);
+                    let mix = in2.clamp(0.0, 1.0);
+                    in0 * (1.0 - mix) + shifted * mix
+                }
+                NodeKind::Vocoder(vocoder) => {
+                    let port = |i: usize| node.input_values.get(i).copied().unwrap_or(0.0);
+                    vocoder
+                        .range(in2, port(3))
+                        .attack(port(4))
+                        .release(port(5))
+                        .formant(port(6))
+                        .output(in0, in1, signal)
+                }
+                NodeKind::PitchShift(shifter, scale) => {
+                    let port = |i: usize| node.input_values.get(i).copied().unwrap_or(0.0);
+                    let semitones = match scale {
+                        Some(scale) => {
+                            let degree = in1.round() as i32;
+                            (scale.note(degree) - scale.note(0)) as f32
+                        }
+                        None => in1,
+                    };
+                    let wet = shifter
+                        .semitones(semitones)
+                        .cents(in2)
+                        .window(port(3))
+                        .output(in0, signal);
+                    let mix = port(4).clamp(0.0, 1.0);
+                    in0 * (1.0 - mix) + wet * mix
+                }
                 NodeKind::Flanger(flanger) => flanger.output(in0, signal),
                 NodeKind::Mul => in0 * in1,
                 NodeKind::Add => in0 + in1,
@@ -450,30 +628,38 @@ impl CompiledVoice {
                         in2
                     }
                 }
-                NodeKind::Rng { last_gate, value } => {
+                NodeKind::Rng {
+                    last_gate,
+                    value,
+                    seed,
+                } => {
                     if in0 > 0.5 && *last_gate <= 0.5 {
-                        *value = fastrand::f32();
+                        *value = next_random(seed);
                     }
                     *last_gate = in0;
                     *value
                 }
-                NodeKind::Sample { samples } => {
-                    if samples.is_empty() {
-                        0.0
-                    } else {
-                        let pos = in0.clamp(0.0, 1.0);
-                        let idx_f = pos * (samples.len() - 1) as f32;
-                        let i = idx_f as usize;
-                        let frac = idx_f - i as f32;
-                        let s0 = samples[i];
-                        let s1 = samples.get(i + 1).copied().unwrap_or(s0);
-                        s0 + frac * (s1 - s0)
+                NodeKind::Sample(samples, channel) => {
+                    let pos = in0.clamp(0.0, 1.0);
+                    let (left, right) = samples.read(pos * samples.len().saturating_sub(1) as f32);
+                    match channel {
+                        SampleChannel::Mix => (left + right) * 0.5,
+                        SampleChannel::Left => left,
+                        SampleChannel::Right => right,
                     }
                 }
                 NodeKind::Probe => in0,
                 NodeKind::Pass => node.input_values.iter().sum(),
                 NodeKind::Output { gain } => in0 * *gain,
             };
+
+            if !node.output.is_finite() {
+                node.kind.clear_state();
+                node.output = 0.0;
+                self.fault = Some(node.module_id);
+            } else {
+                node.output = flush_denormal(node.output);
+            }
         }
 
         self.output_node
@@ -497,8 +683,8 @@ impl PatchVoices {
         let mut sum = 0.0;
         let n = self.voices.len().min(track.num_voices());
         for i in 0..n {
-            let (freq, gate, degree) = track.voice(i);
-            sum += self.voices[i].process(signal, freq, gate, degree);
+            let (freq, gate, degree, velocity) = track.voice(i);
+            sum += self.voices[i].process(signal, freq, gate, degree, velocity, track.lanes());
         }
         sum
     }
@@ -545,6 +731,17 @@ impl CompiledPatch {
 }
 
 impl CompiledPatch {
+    pub fn resolve_lanes(&mut self, lanes: &[Lane]) {
+        let patches = [
+            self.current.as_mut(),
+            self.old.as_mut(),
+            self.pending.as_mut(),
+        ];
+        for voice in patches.into_iter().flatten().flat_map(|p| &mut p.voices) {
+            voice.resolve_lanes(lanes);
+        }
+    }
+
     fn set_voices(&mut self, voices: Vec<CompiledVoice>, immediate: bool) {
         let mut new_patch = PatchVoices { voices };
         if let Some(ref current) = self.current {
@@ -609,6 +806,7 @@ impl CompiledPatch {
 
         let sample = sample.clamp(-1.0, 1.0);
 
+        let mut clear_faults = false;
         if let Some(ref current) = self.current {
             let voice_idx = self.probe_voice.min(current.voices.len().saturating_sub(1));
             if let Some(voice) = current.voices.get(voice_idx) {
@@ -656,17 +854,29 @@ impl CompiledPatch {
                         for buf in &mut self.probe_buffers {
                             buf.clear();
                         }
+                        let mut faults: Vec<ModuleId> =
+                            current.voices.iter().filter_map(|v| v.fault).collect();
+                        faults.sort_by_key(|id| id.0);
+                        faults.dedup();
+                        clear_faults = true;
                         let _ = tx.try_send(MeterFrame {
                             instrument_idx,
                             ports,
                             probes,
                             active_pitches: track.active_pitches(),
+                            faults,
                         });
                     }
                 }
             }
         }
 
+        if clear_faults && let Some(current) = self.current.as_mut() {
+            for voice in &mut current.voices {
+                voice.fault = None;
+            }
+        }
+
         sample
     }
 
@@ -689,10 +899,13 @@ impl CompiledPatch {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct CompileContext {
     pub sample_rate: f32,
     pub bpm: f32,
-    pub bars: f32,
+    pub beats: f32,
+    pub scale: Scale,
+    pub seed: u64,
 }
 
 impl Default for CompileContext {
@@ -700,7 +913,9 @@ impl Default for CompileContext {
         Self {
             sample_rate: 44100.0,
             bpm: 120.0,
-            bars: 1.0,
+            beats: 4.0,
+            scale: cmaj(),
+            seed: 0,
         }
     }
 }
@@ -723,7 +938,13 @@ pub fn compile_voices(
     let (modules, connections) = flatten_patchset(patches);
     let module_refs: Vec<&Module> = modules.iter().collect();
     (0..num_voices)
-        .map(|_| compile_voice(&module_refs, &connections, ctx))
+        .map(|voice| {
+            let voice_ctx = CompileContext {
+                seed: derive_seed(ctx.seed, voice as u64) as u64,
+                ..*ctx
+            };
+            compile_voice(&module_refs, &connections, &voice_ctx)
+        })
         .collect()
 }
 
@@ -792,26 +1013,28 @@ impl AudioEngine {
                 idx,
                 voices,
                 track,
-                bars,
+                beats,
                 immediate,
             } => {
                 self.ensure_instruments(idx + 1);
                 if let Some(inst) = self.instruments.get_mut(idx) {
                     inst.patch.set_voices(voices, immediate);
                     inst.track.set_track(track);
-                    inst.track.clock.bars(bars);
+                    inst.track.clock.beats(beats);
+                    inst.patch.resolve_lanes(inst.track.lanes());
                 }
             }
             AudioCommand::SetVoices {
                 idx,
                 voices,
-                bars,
+                beats,
                 immediate,
             } => {
                 self.ensure_instruments(idx + 1);
                 if let Some(inst) = self.instruments.get_mut(idx) {
                     inst.patch.set_voices(voices, immediate);
-                    inst.track.clock.bars(bars);
+                    inst.track.clock.beats(beats);
+                    inst.patch.resolve_lanes(inst.track.lanes());
                 }
             }
             AudioCommand::SetProbeVoice(voice) => {
@@ -836,7 +1059,8 @@ impl AudioEngine {
         for (idx, inst) in self.instruments.iter_mut().enumerate() {
             sum += inst.process(signal, idx);
         }
-        sum.clamp(-1.0, 1.0)
+        signal.add_sample(sum);
+        signal.mix()
     }
 }
 
@@ -1120,6 +1344,7 @@ fn compile_voice(
         execution_order: Vec::new(),
         output_node: None,
         last_gate: 1.0,
+        fault: None,
     };
     let mut module_to_node: HashMap<ModuleId, usize> = HashMap::new();
 
@@ -1287,6 +1512,13 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
         (ModuleKind::Standard(StandardModule::Freq), _) => NodeKind::Freq,
         (ModuleKind::Standard(StandardModule::Gate), _) => NodeKind::Gate,
         (ModuleKind::Standard(StandardModule::Degree), _) => NodeKind::Degree,
+        (ModuleKind::Standard(StandardModule::Velocity), _) => NodeKind::Velocity,
+        (ModuleKind::Standard(StandardModule::Lane), ModuleParams::Lane { name }) => {
+            NodeKind::Lane {
+                name: name.clone(),
+                index: None,
+            }
+        }
         (ModuleKind::Standard(StandardModule::DegreeGate), ModuleParams::DegreeGate { degree }) => {
             NodeKind::DegreeGate { target: *degree }
         }
@@ -1303,23 +1535,24 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
             if *uni {
                 osc.unipolar();
             }
+            osc.seed(derive_seed(ctx.seed, module.id.0 as u64));
             NodeKind::Oscillator(osc)
         }
         (ModuleKind::Standard(StandardModule::Rise), ModuleParams::Rise { time, .. }) => {
             let mut ramp = GateRamp::default();
             ramp.rise();
-            ramp.time(time.as_seconds(ctx.bpm, ctx.bars));
+            ramp.time(time.as_seconds(ctx.bpm, ctx.beats));
             NodeKind::Rise(ramp)
         }
         (ModuleKind::Standard(StandardModule::Fall), ModuleParams::Fall { time, .. }) => {
             let mut ramp = GateRamp::default();
             ramp.fall();
-            ramp.time(time.as_seconds(ctx.bpm, ctx.bars));
+            ramp.time(time.as_seconds(ctx.bpm, ctx.beats));
             NodeKind::Fall(ramp)
         }
         (ModuleKind::Standard(StandardModule::Ramp), ModuleParams::Ramp { time, .. }) => {
             let mut ramp = Ramp::default();
-            ramp.time(time.as_seconds(ctx.bpm, ctx.bars));
+            ramp.time(time.as_seconds(ctx.bpm, ctx.beats));
             NodeKind::Ramp(ramp)
         }
         (
@@ -1370,7 +1603,7 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
                 ..
             },
         ) => {
-            let size = time.as_samples(ctx.sample_rate, ctx.bpm, ctx.bars) as usize;
+            let size = time.as_samples(ctx.sample_rate, ctx.bpm, ctx.beats) as usize;
             let mut comb = CombFilter::new(size.max(1));
             comb.feedback(*feedback).damp(*damp);
             NodeKind::Comb(comb)
@@ -1379,14 +1612,14 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
             ModuleKind::Standard(StandardModule::Allpass),
             ModuleParams::Allpass { time, feedback, .. },
         ) => {
-            let size = time.as_samples(ctx.sample_rate, ctx.bpm, ctx.bars) as usize;
+            let size = time.as_samples(ctx.sample_rate, ctx.bpm, ctx.beats) as usize;
             let mut allpass = AllpassFilter::new(size.max(1));
             allpass.feedback(*feedback);
             NodeKind::Allpass(allpass)
         }
         (ModuleKind::Standard(StandardModule::Delay), ModuleParams::Delay { time, .. }) => {
             let mut delay = Delay::default();
-            delay.delay(time.as_samples(ctx.sample_rate, ctx.bpm, ctx.bars));
+            delay.delay(time.as_samples(ctx.sample_rate, ctx.bpm, ctx.beats));
             NodeKind::Delay(delay)
         }
         (ModuleKind::Standard(StandardModule::DelayTap(_)), ModuleParams::DelayTap { gain }) => {
@@ -1419,15 +1652,90 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
                 dist_type,
                 drive,
                 asymmetry,
+                oversample,
                 ..
             },
         ) => {
             let mut dist = Distortion::default();
             dist.dist_type(dist_type.to_dsp())
                 .drive(*drive)
-                .asymmetry(*asymmetry);
+                .asymmetry(*asymmetry)
+                .oversample(oversample.to_dsp());
             NodeKind::Distortion(dist)
         }
+        (
+            ModuleKind::Standard(StandardModule::Crush),
+            ModuleParams::Crush {
+                bits,
+                rate,
+                jitter,
+                drive,
+                mix,
+                dither,
+                anti_alias,
+                ..
+            },
+        ) => {
+            let mut crush = Bitcrusher::default();
+            crush
+                .bits(*bits)
+                .rate(*rate)
+                .jitter(*jitter)
+                .drive(*drive)
+                .mix(*mix)
+                .dither(*dither)
+                .anti_alias(*anti_alias)
+                .seed(derive_seed(ctx.seed, module.id.0 as u64));
+            NodeKind::Crush(crush)
+        }
+        (ModuleKind::Standard(StandardModule::Ring), ModuleParams::Ring { freq, mix, .. }) => {
+            let mut ring = RingModulator::default();
+            ring.freq(freq.as_hz(ctx.bpm, ctx.beats)).mix(*mix);
+            NodeKind::Ring(ring)
+        }
+        (
+            ModuleKind::Standard(StandardModule::FreqShift),
+            ModuleParams::FreqShift { shift, .. },
+        ) => {
+            let mut shifter = FrequencyShifter::default();
+            shifter.shift(*shift);
+            NodeKind::FreqShift(shifter)
+        }
+        (
+            ModuleKind::Standard(StandardModule::Vocoder),
+            ModuleParams::Vocoder {
+                bands,
+                low,
+                high,
+                attack,
+                release,
+                formant,
+                ..
+            },
+        ) => {
+            let mut vocoder = Vocoder::default();
+            vocoder
+                .bands(*bands as usize)
+                .range(*low, *high)
+                .attack(*attack)
+                .release(*release)
+                .formant(*formant);
+            NodeKind::Vocoder(vocoder)
+        }
+        (
+            ModuleKind::Standard(StandardModule::PitchShift),
+            ModuleParams::PitchShift {
+                semitones,
+                cents,
+                window,
+                degrees,
+                ..
+            },
+        ) => {
+            let mut shifter = PitchShifter::default();
+            shifter.semitones(*semitones).cents(*cents).window(*window);
+            NodeKind::PitchShift(shifter, degrees.then_some(ctx.scale))
+        }
         (
             ModuleKind::Standard(StandardModule::Flanger),
             ModuleParams::Flanger {
@@ -1449,12 +1757,14 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
         (ModuleKind::Standard(StandardModule::Rng), _) => NodeKind::Rng {
             last_gate: 0.0,
             value: 0.0,
+            seed: derive_seed(ctx.seed, module.id.0 as u64),
         },
-        (ModuleKind::Standard(StandardModule::Sample), ModuleParams::Sample { samples, .. }) => {
-            NodeKind::Sample {
-                samples: samples.clone(),
-            }
-        }
+        (
+            ModuleKind::Standard(StandardModule::Sample),
+            ModuleParams::Sample {
+                samples, channel, ..
+            },
+        ) => NodeKind::Sample(samples.clone(), *channel),
         (ModuleKind::Standard(StandardModule::Probe), _) => NodeKind::Probe,
         (ModuleKind::Standard(StandardModule::Output), ModuleParams::Output { gain, .. }) => {
             NodeKind::Output { gain: *gain }
@@ -1474,8 +1784,14 @@ fn create_node_kind(module: &Module, ctx: &CompileContext) -> NodeKind {
         | (ModuleKind::Standard(StandardModule::DelayTap(_)), _)
         | (ModuleKind::Standard(StandardModule::Reverb), _)
         | (ModuleKind::Standard(StandardModule::Distortion), _)
+        | (ModuleKind::Standard(StandardModule::Crush), _)
+        | (ModuleKind::Standard(StandardModule::Ring), _)
+        | (ModuleKind::Standard(StandardModule::FreqShift), _)
+        | (ModuleKind::Standard(StandardModule::Vocoder), _)
+        | (ModuleKind::Standard(StandardModule::PitchShift), _)
         | (ModuleKind::Standard(StandardModule::Flanger), _)
         | (ModuleKind::Standard(StandardModule::Sample), _)
+        | (ModuleKind::Standard(StandardModule::Lane), _)
         | (ModuleKind::Standard(StandardModule::Output), _) => {
             unreachable!(
                 "ModuleKind {:?} matched with wrong ModuleParams {:?}",
@@ -1508,7 +1824,7 @@ fn get_input_defaults(module: &Module, ctx: &CompileContext) -> Vec<f32> {
                 module
                     .params
                     .get_time(i)
-                    .map(|t| t.as_hz(ctx.bpm, ctx.bars))
+                    .map(|t| t.as_hz(ctx.bpm, ctx.beats))
                     .unwrap_or(440.0)
             } else {
                 module.params.get_float(i).unwrap_or(0.0)
@@ -1521,6 +1837,7 @@ fn get_input_defaults(module: &Module, ctx: &CompileContext) -> Vec<f32> {
 mod tests {
     use super::*;
     use crate::tui::grid::GridPos;
+    use crate::tui::module::ModuleParams;
 
     use ratatui::style::Color;
 
@@ -1825,14 +2142,14 @@ mod tests {
         let mut voice = compile_voice(&module_refs, &connections, &ctx);
         let mut signal = Signal::new(44100);
 
-        let output = voice.process(&mut signal, 440.0, 1.0, 0);
+        let output = voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]);
         assert!(
             (output - 1.0).abs() < 0.001,
             "Output should be 1.0 (gate), got {}",
             output
         );
 
-        let output = voice.process(&mut signal, 440.0, 0.0, 0);
+        let output = voice.process(&mut signal, 440.0, 0.0, 0, 1.0, &[]);
         assert!(
             output.abs() < 0.001,
             "Output should be 0.0 (gate off), got {}",
@@ -1840,6 +2157,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_non_finite_node_is_reset_and_reported() {
+        use crate::Signal;
+        let mut patches = PatchSet::new(20, 20);
+
+        let freq_id = patches.alloc_module_id();
+        patches.add_module(
+            None,
+            Module::new(freq_id, ModuleKind::Standard(StandardModule::Freq)),
+            GridPos::new(0, 0),
+        );
+        let id = patches.alloc_module_id();
+        patches.add_module(
+            None,
+            Module::new(id, ModuleKind::Standard(StandardModule::Output)),
+            GridPos::new(1, 0),
+        );
+        patches.root_mut().rebuild_channels();
+
+        let (modules, connections) = flatten_patchset(&patches);
+        let module_refs: Vec<&Module> = modules.iter().collect();
+        let ctx = CompileContext::default();
+        let mut voice = compile_voice(&module_refs, &connections, &ctx);
+        let mut signal = Signal::new(44100);
+
+        let output = voice.process(&mut signal, f32::NAN, 1.0, 0, 1.0, &[]);
+        assert_eq!(output, 0.0);
+        assert_eq!(voice.fault, Some(freq_id));
+
+        let output = voice.process(&mut signal, 1e-30, 1.0, 0, 1.0, &[]);
+        assert_eq!(output, 0.0, "denormal-range values should be flushed");
+    }
+
+    #[test]
+    fn test_poisoned_node_state_recovers() {
+        use crate::Signal;
+        let mut patches = PatchSet::new(20, 20);
+        for (x, kind) in [StandardModule::Ramp, StandardModule::Output]
+            .into_iter()
+            .enumerate()
+        {
+            let id = patches.alloc_module_id();
+            patches.add_module(
+                None,
+                Module::new(id, ModuleKind::Standard(kind)),
+                GridPos::new(x as u16, 0),
+            );
+        }
+        patches.root_mut().rebuild_channels();
+
+        let mut voice = compile_voices(&patches, 1, &CompileContext::default()).remove(0);
+        let mut signal = Signal::new(44100);
+        voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]);
+        for node in &mut voice.nodes {
+            if let NodeKind::Ramp(ramp) = &mut node.kind {
+                ramp.current_value = Some(f32::NAN);
+            }
+        }
+
+        assert_eq!(voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]), 0.0);
+        assert!(voice.fault.is_some());
+        voice.fault = None;
+        for _ in 0..4 {
+            assert!(
+                voice
+                    .process(&mut signal, 440.0, 1.0, 0, 1.0, &[])
+                    .is_finite()
+            );
+        }
+        assert_eq!(voice.fault, None, "reset should clear the poisoned state");
+    }
+
+    #[test]
+    fn test_render_seed_is_reproducible() {
+        use crate::Signal;
+        let mut patches = PatchSet::new(20, 20);
+        for (x, kind) in [
+            StandardModule::Gate,
+            StandardModule::Rng,
+            StandardModule::Output,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let id = patches.alloc_module_id();
+            patches.add_module(
+                None,
+                Module::new(id, ModuleKind::Standard(kind)),
+                GridPos::new(x as u16, 0),
+            );
+        }
+        patches.root_mut().rebuild_channels();
+
+        let render = |seed: u64| {
+            let ctx = CompileContext {
+                seed,
+                ..CompileContext::default()
+            };
+            let mut voices = compile_voices(&patches, 2, &ctx);
+            let mut signal = Signal::new(44100);
+            let mut out = Vec::new();
+            for i in 0..8 {
+                let gate = (i % 2) as f32;
+                for voice in &mut voices {
+                    out.push(voice.process(&mut signal, 440.0, gate, 0, 1.0, &[]));
+                }
+            }
+            out
+        };
+
+        assert_eq!(render(7), render(7));
+        assert_ne!(render(7), render(8));
+        let first_gate = &render(7)[2..4];
+        assert_ne!(
+            first_gate[0], first_gate[1],
+            "voices should get distinct streams"
+        );
+    }
+
     #[test]
     fn test_freq_inside_subpatch() {
         use crate::Signal;
@@ -1883,14 +2319,14 @@ mod tests {
         let mut voice = compile_voice(&module_refs, &connections, &ctx);
         let mut signal = Signal::new(44100);
 
-        let output = voice.process(&mut signal, 440.0, 1.0, 0);
+        let output = voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]);
         assert!(
             (output - 440.0).abs() < 0.001,
             "Output should be 440.0 (freq), got {}",
             output
         );
 
-        let output = voice.process(&mut signal, 880.0, 1.0, 0);
+        let output = voice.process(&mut signal, 880.0, 1.0, 0, 1.0, &[]);
         assert!(
             (output - 880.0).abs() < 0.001,
             "Output should be 880.0 (freq), got {}",
@@ -1898,6 +2334,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lane_reads_track_value() {
+        use crate::Signal;
+        let mut patches = PatchSet::new(20, 20);
+        let mut lane = Module::new(
+            patches.alloc_module_id(),
+            ModuleKind::Standard(StandardModule::Lane),
+        );
+        lane.params = ModuleParams::Lane {
+            name: "cutoff".into(),
+        };
+        patches.add_module(None, lane, GridPos::new(0, 0));
+        let id = patches.alloc_module_id();
+        patches.add_module(
+            None,
+            Module::new(id, ModuleKind::Standard(StandardModule::Output)),
+            GridPos::new(2, 0),
+        );
+        patches.root_mut().rebuild_channels();
+
+        let (modules, connections) = flatten_patchset(&patches);
+        let module_refs: Vec<&Module> = modules.iter().collect();
+        let mut voice = compile_voice(&module_refs, &connections, &CompileContext::default());
+        let mut signal = Signal::new(44100);
+
+        let mut track = Track::parse("(0)\namp: (1)\ncutoff: (0.25/0.75)", &cmaj()).unwrap();
+        track.set_playhead(0.6);
+        voice.resolve_lanes(track.lanes());
+        let output = voice.process(&mut signal, 440.0, 1.0, 0, 1.0, track.lanes());
+        // Scaled by the Output module's default gain of 0.5.
+        assert!((output - 0.375).abs() < 0.001, "got {}", output);
+
+        let output = voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]);
+        assert_eq!(output, 0.0);
+    }
+
+    #[test]
+    fn test_engine_output_runs_through_mix_bus() {
+        use crate::{BusStage, Signal};
+        let mut patches = PatchSet::new(20, 20);
+        for (x, kind) in [StandardModule::Gate, StandardModule::Output]
+            .into_iter()
+            .enumerate()
+        {
+            let id = patches.alloc_module_id();
+            patches.add_module(
+                None,
+                Module::new(id, ModuleKind::Standard(kind)),
+                GridPos::new(x as u16, 0),
+            );
+        }
+        patches.root_mut().rebuild_channels();
+
+        let (cmd_tx, cmd_rx) = command_channel();
+        let (meter_tx, _meter_rx) = meter_channel();
+        let mut engine = AudioEngine::new(1, 44100.0, cmd_rx, meter_tx);
+        for idx in 0..3 {
+            cmd_tx
+                .send(AudioCommand::SetInstrument {
+                    idx,
+                    voices: compile_voices(&patches, 1, &CompileContext::default()),
+                    track: Some(Track::parse("(0)", &cmaj()).unwrap()),
+                    beats: 4.0,
+                    immediate: true,
+                })
+                .unwrap();
+        }
+        engine.poll_commands();
+
+        let mut signal = Signal::new(44100);
+        signal.bus.stage(BusStage::Limiter);
+        let mut peak: f32 = 0.0;
+        for _ in 0..1000 {
+            peak = peak.max(engine.process(&mut signal).abs());
+            signal.advance();
+        }
+        // Three gates at the Output module's 0.5 gain sum past full scale.
+        assert!(peak > 0.99 && peak <= 1.0, "got {}", peak);
+
+        signal.global_volume = 0.0;
+        for _ in 0..44100 {
+            engine.process(&mut signal);
+            signal.advance();
+        }
+        assert!(engine.process(&mut signal).abs() < 1e-3);
+    }
+
     #[test]
     fn test_delay_tap_linking() {
         let mut patches = PatchSet::new(20, 20);
@@ -1992,7 +2515,7 @@ mod tests {
         let mut signal = Signal::new(44100);
 
         for _ in 0..1000 {
-            voice.process(&mut signal, 440.0, 1.0, 0);
+            voice.process(&mut signal, 440.0, 1.0, 0, 1.0, &[]);
         }
 
         let tap_node_idx = voice
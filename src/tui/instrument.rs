@@ -1,12 +1,22 @@
 use super::grid::GridPos;
 use super::module::{Module, ModuleId, ModuleKind, StandardModule, SubPatchId};
 use super::patch::PatchSet;
+use crate::scale::{Scale, cmin};
+use crate::track::TrackParseError;
+use crate::{Groove, TimeSignature, Tuning};
 use std::collections::{HashMap, VecDeque};
 
 pub struct Instrument {
     pub patches: PatchSet,
     pub track_text: String,
-    pub scale_idx: usize,
+    pub track_errors: Vec<TrackParseError>,
+    // Time signature of every bar, from the last track that parsed.
+    pub meters: Vec<TimeSignature>,
+    pub scale: Scale,
+    pub tuning_name: String,
+    pub tuning: Tuning,
+    pub swing: f32,
+    pub groove: Groove,
     pub cursor: GridPos,
     pub view_center: GridPos,
     pub editing_subpatch: Option<SubPatchId>,
@@ -44,7 +54,13 @@ impl Instrument {
         Self {
             patches,
             track_text,
-            scale_idx: 2,
+            track_errors: Vec::new(),
+            meters: vec![TimeSignature::default()],
+            scale: cmin(),
+            tuning_name: "12-EDO".to_string(),
+            tuning: Tuning::default(),
+            swing: 50.0,
+            groove: Groove::straight(),
             cursor: GridPos::new(0, 0),
             view_center: GridPos::new(0, 0),
             editing_subpatch: None,
@@ -163,6 +163,82 @@ impl DistType {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Oversample {
+    #[default]
+    X2,
+    X4,
+    X8,
+}
+
+impl Oversample {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Oversample::X2 => "2x",
+            Oversample::X4 => "4x",
+            Oversample::X8 => "8x",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Oversample::X2 => Oversample::X4,
+            Oversample::X4 => Oversample::X8,
+            Oversample::X8 => Oversample::X2,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            Oversample::X2 => Oversample::X8,
+            Oversample::X4 => Oversample::X2,
+            Oversample::X8 => Oversample::X4,
+        }
+    }
+
+    pub fn to_dsp(self) -> crate::oversampler::OversampleFactor {
+        match self {
+            Oversample::X2 => crate::oversampler::OversampleFactor::X2,
+            Oversample::X4 => crate::oversampler::OversampleFactor::X4,
+            Oversample::X8 => crate::oversampler::OversampleFactor::X8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SampleChannel {
+    #[default]
+    Mix,
+    Left,
+    Right,
+}
+
+impl SampleChannel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SampleChannel::Mix => "Mix",
+            SampleChannel::Left => "L",
+            SampleChannel::Right => "R",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            SampleChannel::Mix => SampleChannel::Left,
+            SampleChannel::Left => SampleChannel::Right,
+            SampleChannel::Right => SampleChannel::Mix,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            SampleChannel::Mix => SampleChannel::Right,
+            SampleChannel::Left => SampleChannel::Mix,
+            SampleChannel::Right => SampleChannel::Left,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RoutingModule {
     LSplit,
@@ -185,6 +261,8 @@ pub enum StandardModule {
     Freq,
     Gate,
     Degree,
+    Velocity,
+    Lane,
     DegreeGate,
     Osc,
     Rise,
@@ -200,6 +278,11 @@ pub enum StandardModule {
     DelayTap(ModuleId),
     Reverb,
     Distortion,
+    Crush,
+    Ring,
+    FreqShift,
+    Vocoder,
+    PitchShift,
     Flanger,
     Mul,
     Add,
@@ -239,6 +322,8 @@ impl ModuleKind {
                 StandardModule::Freq => "Freq",
                 StandardModule::Gate => "Gate",
                 StandardModule::Degree => "Deg",
+                StandardModule::Velocity => "Vel",
+                StandardModule::Lane => "Lane",
                 StandardModule::DegreeGate => "DegG",
                 StandardModule::Osc => "Osc",
                 StandardModule::Rise => "Rise",
@@ -254,6 +339,11 @@ impl ModuleKind {
                 StandardModule::DelayTap(_) => "Tap",
                 StandardModule::Reverb => "Verb",
                 StandardModule::Distortion => "Dist",
+                StandardModule::Crush => "Crush",
+                StandardModule::Ring => "Ring",
+                StandardModule::FreqShift => "Shift",
+                StandardModule::Vocoder => "Vocod",
+                StandardModule::PitchShift => "Pitch",
                 StandardModule::Flanger => "Flang",
                 StandardModule::Mul => "Mul",
                 StandardModule::Add => "Add",
@@ -287,6 +377,8 @@ impl ModuleKind {
                 StandardModule::Freq => "FRQ",
                 StandardModule::Gate => "GAT",
                 StandardModule::Degree => "DEG",
+                StandardModule::Velocity => "VEL",
+                StandardModule::Lane => "LNE",
                 StandardModule::DegreeGate => "DGG",
                 StandardModule::Osc => "OSC",
                 StandardModule::Rise => "RIS",
@@ -302,6 +394,11 @@ impl ModuleKind {
                 StandardModule::DelayTap(_) => "TAP",
                 StandardModule::Reverb => "VRB",
                 StandardModule::Distortion => "DST",
+                StandardModule::Crush => "CRU",
+                StandardModule::Ring => "RMD",
+                StandardModule::FreqShift => "FSH",
+                StandardModule::Vocoder => "VOC",
+                StandardModule::PitchShift => "PSH",
                 StandardModule::Flanger => "FLG",
                 StandardModule::Mul => "MUL",
                 StandardModule::Add => "ADD",
@@ -335,6 +432,8 @@ impl ModuleKind {
                 StandardModule::Freq => "Note frequency from track",
                 StandardModule::Gate => "Note gate - on / off",
                 StandardModule::Degree => "Scale degree from track",
+                StandardModule::Velocity => "Note velocity from track",
+                StandardModule::Lane => "Automation lane from track",
                 StandardModule::DegreeGate => "Gate when degree matches",
                 StandardModule::Osc => "Oscillator - makes noise!",
                 StandardModule::Rise => "Ramps 0->1 while gate high",
@@ -350,6 +449,11 @@ impl ModuleKind {
                 StandardModule::DelayTap(_) => "Read from delay (feedback)",
                 StandardModule::Reverb => "FDN reverb with modulation",
                 StandardModule::Distortion => "Soft-clip distortion",
+                StandardModule::Crush => "Bit crusher and decimator",
+                StandardModule::Ring => "Ring mod with sine carrier",
+                StandardModule::FreqShift => "Freq shifter (+Hz up, -Hz down)",
+                StandardModule::Vocoder => "Channel vocoder (carrier, mod)",
+                StandardModule::PitchShift => "Delay-line pitch shifter",
                 StandardModule::Flanger => "Flanger/chorus effect",
                 StandardModule::Mul => "Multiply A * B",
                 StandardModule::Add => "Add A + B",
@@ -375,6 +479,8 @@ impl ModuleKind {
                 StandardModule::Freq
                 | StandardModule::Gate
                 | StandardModule::Degree
+                | StandardModule::Velocity
+                | StandardModule::Lane
                 | StandardModule::DegreeGate => Color::Rgb(100, 200, 100),
                 StandardModule::Osc | StandardModule::Sample => Color::Rgb(100, 150, 255),
                 StandardModule::Rise
@@ -390,6 +496,11 @@ impl ModuleKind {
                 | StandardModule::DelayTap(_)
                 | StandardModule::Reverb
                 | StandardModule::Distortion
+                | StandardModule::Crush
+                | StandardModule::Ring
+                | StandardModule::FreqShift
+                | StandardModule::Vocoder
+                | StandardModule::PitchShift
                 | StandardModule::Flanger => Color::Rgb(200, 100, 255),
                 StandardModule::Mul
                 | StandardModule::Add
@@ -451,6 +562,8 @@ impl ModuleKind {
                 StandardModule::Freq
                 | StandardModule::Gate
                 | StandardModule::Degree
+                | StandardModule::Velocity
+                | StandardModule::Lane
                 | StandardModule::DegreeGate
                 | StandardModule::Osc
                 | StandardModule::Rise
@@ -466,6 +579,11 @@ impl ModuleKind {
                 | StandardModule::DelayTap(_)
                 | StandardModule::Reverb
                 | StandardModule::Distortion
+                | StandardModule::Crush
+                | StandardModule::Ring
+                | StandardModule::FreqShift
+                | StandardModule::Vocoder
+                | StandardModule::PitchShift
                 | StandardModule::Flanger
                 | StandardModule::Mul
                 | StandardModule::Add
@@ -502,6 +620,8 @@ impl ModuleKind {
                 StandardModule::Freq
                 | StandardModule::Gate
                 | StandardModule::Degree
+                | StandardModule::Velocity
+                | StandardModule::Lane
                 | StandardModule::DegreeGate
                 | StandardModule::Osc
                 | StandardModule::Rise
@@ -516,6 +636,11 @@ impl ModuleKind {
                 | StandardModule::DelayTap(_)
                 | StandardModule::Reverb
                 | StandardModule::Distortion
+                | StandardModule::Crush
+                | StandardModule::Ring
+                | StandardModule::FreqShift
+                | StandardModule::Vocoder
+                | StandardModule::PitchShift
                 | StandardModule::Flanger
                 | StandardModule::Mul
                 | StandardModule::Add
@@ -537,6 +662,8 @@ impl ModuleKind {
                 StandardModule::Freq
                 | StandardModule::Gate
                 | StandardModule::Degree
+                | StandardModule::Velocity
+                | StandardModule::Lane
                 | StandardModule::DegreeGate => ModuleCategory::Track,
                 StandardModule::Osc | StandardModule::Sample => ModuleCategory::Generator,
                 StandardModule::Rise
@@ -552,6 +679,11 @@ impl ModuleKind {
                 | StandardModule::DelayTap(_)
                 | StandardModule::Reverb
                 | StandardModule::Distortion
+                | StandardModule::Crush
+                | StandardModule::Ring
+                | StandardModule::FreqShift
+                | StandardModule::Vocoder
+                | StandardModule::PitchShift
                 | StandardModule::Flanger => ModuleCategory::Effect,
                 StandardModule::Mul
                 | StandardModule::Add
@@ -573,6 +705,8 @@ impl ModuleKind {
             ModuleKind::Standard(Freq),
             ModuleKind::Standard(Gate),
             ModuleKind::Standard(Degree),
+            ModuleKind::Standard(Velocity),
+            ModuleKind::Standard(Lane),
             ModuleKind::Standard(DegreeGate),
             ModuleKind::Standard(Osc),
             ModuleKind::Standard(Rise),
@@ -588,6 +722,11 @@ impl ModuleKind {
             ModuleKind::Standard(DelayTap(ModuleId(0))),
             ModuleKind::Standard(Reverb),
             ModuleKind::Standard(Distortion),
+            ModuleKind::Standard(Crush),
+            ModuleKind::Standard(Ring),
+            ModuleKind::Standard(FreqShift),
+            ModuleKind::Standard(Vocoder),
+            ModuleKind::Standard(PitchShift),
             ModuleKind::Standard(Flanger),
             ModuleKind::Standard(Probe),
             ModuleKind::Standard(Mul),
@@ -917,6 +1056,7 @@ impl Module {
             },
             ModuleParams::None
             | ModuleParams::DegreeGate { .. }
+            | ModuleParams::Lane { .. }
             | ModuleParams::Rise { .. }
             | ModuleParams::Fall { .. }
             | ModuleParams::Ramp { .. }
@@ -928,6 +1068,11 @@ impl Module {
             | ModuleParams::Delay { .. }
             | ModuleParams::Reverb { .. }
             | ModuleParams::Distortion { .. }
+            | ModuleParams::Crush { .. }
+            | ModuleParams::Ring { .. }
+            | ModuleParams::FreqShift { .. }
+            | ModuleParams::Vocoder { .. }
+            | ModuleParams::PitchShift { .. }
             | ModuleParams::Flanger { .. }
             | ModuleParams::Mul { .. }
             | ModuleParams::Add { .. }
@@ -1073,31 +1218,33 @@ impl TimeValue {
         }
     }
 
-    pub fn as_hz(self, bpm: f32, bars: f32) -> f32 {
-        1.0 / self.as_seconds(bpm, bars)
+    // `beats` is the length of the track in quarter notes; `Bars` values
+    // are fractions of the whole track.
+    pub fn as_hz(self, bpm: f32, beats: f32) -> f32 {
+        1.0 / self.as_seconds(bpm, beats)
     }
 
-    pub fn as_samples(self, sample_rate: f32, bpm: f32, bars: f32) -> f32 {
+    pub fn as_samples(self, sample_rate: f32, bpm: f32, beats: f32) -> f32 {
         match self.unit {
             TimeUnit::Seconds => self.seconds * sample_rate,
             TimeUnit::Samples => self.samples,
             TimeUnit::Bars => {
                 let bar_fraction = self.bar_num as f32 / self.bar_denom as f32;
-                let seconds_per_bar = (60.0 / bpm) * 4.0 * bars;
-                bar_fraction * seconds_per_bar * sample_rate
+                let seconds_per_loop = (60.0 / bpm) * beats;
+                bar_fraction * seconds_per_loop * sample_rate
             }
             TimeUnit::Hz => sample_rate / self.hz,
         }
     }
 
-    pub fn as_seconds(self, bpm: f32, bars: f32) -> f32 {
+    pub fn as_seconds(self, bpm: f32, beats: f32) -> f32 {
         match self.unit {
             TimeUnit::Seconds => self.seconds,
             TimeUnit::Samples => self.samples / 44100.0,
             TimeUnit::Bars => {
                 let bar_fraction = self.bar_num as f32 / self.bar_denom as f32;
-                let seconds_per_bar = (60.0 / bpm) * 4.0 * bars;
-                bar_fraction * seconds_per_bar
+                let seconds_per_loop = (60.0 / bpm) * beats;
+                bar_fraction * seconds_per_loop
             }
             TimeUnit::Hz => 1.0 / self.hz,
         }
@@ -1258,7 +1405,15 @@ impl ModuleKind {
                 }],
             },
             ModuleKind::Standard(s) => match s {
-                StandardModule::Freq | StandardModule::Gate | StandardModule::Degree => &[],
+                StandardModule::Freq
+                | StandardModule::Gate
+                | StandardModule::Degree
+                | StandardModule::Velocity => &[],
+                StandardModule::Lane => &[ParamDef {
+                    name: "Lane",
+                    kind: ParamKind::Enum,
+                    desc: None,
+                }],
                 StandardModule::DegreeGate => &[ParamDef {
                     name: "Deg",
                     kind: ParamKind::Int { min: 0, max: 12 },
@@ -1595,6 +1750,245 @@ impl ModuleKind {
                         },
                         desc: None,
                     },
+                    ParamDef {
+                        name: "Over",
+                        kind: ParamKind::Enum,
+                        desc: Some("Oversampling factor"),
+                    },
+                ],
+                StandardModule::Crush => &[
+                    ParamDef {
+                        name: "In",
+                        kind: ParamKind::Float {
+                            min: -1.0,
+                            max: 1.0,
+                            step: 0.01,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Bits",
+                        kind: ParamKind::Float {
+                            min: 1.0,
+                            max: 16.0,
+                            step: 0.5,
+                        },
+                        desc: Some("Bit depth"),
+                    },
+                    ParamDef {
+                        name: "Rate",
+                        kind: ParamKind::Float {
+                            min: 1.0,
+                            max: 64.0,
+                            step: 1.0,
+                        },
+                        desc: Some("Hold each sample for N samples"),
+                    },
+                    ParamDef {
+                        name: "Jit",
+                        kind: ParamKind::Float {
+                            min: 0.0,
+                            max: 1.0,
+                            step: 0.05,
+                        },
+                        desc: Some("Random variation of the hold length"),
+                    },
+                    ParamDef {
+                        name: "Drive",
+                        kind: ParamKind::Float {
+                            min: 0.1,
+                            max: 20.0,
+                            step: 0.1,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Mix",
+                        kind: ParamKind::Float {
+                            min: 0.0,
+                            max: 1.0,
+                            step: 0.05,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Dith",
+                        kind: ParamKind::Toggle,
+                        desc: Some("Add TPDF dither before quantizing"),
+                    },
+                    ParamDef {
+                        name: "AA",
+                        kind: ParamKind::Toggle,
+                        desc: Some("Lowpass before decimating"),
+                    },
+                ],
+                StandardModule::Ring => &[
+                    ParamDef {
+                        name: "In",
+                        kind: ParamKind::Float {
+                            min: -1.0,
+                            max: 1.0,
+                            step: 0.01,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Freq",
+                        kind: ParamKind::Time,
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Mix",
+                        kind: ParamKind::Float {
+                            min: 0.0,
+                            max: 1.0,
+                            step: 0.05,
+                        },
+                        desc: None,
+                    },
+                ],
+                StandardModule::FreqShift => &[
+                    ParamDef {
+                        name: "In",
+                        kind: ParamKind::Float {
+                            min: -1.0,
+                            max: 1.0,
+                            step: 0.01,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Hz",
+                        kind: ParamKind::Float {
+                            min: -2000.0,
+                            max: 2000.0,
+                            step: 1.0,
+                        },
+                        desc: Some("Shift amount, negative shifts down"),
+                    },
+                    ParamDef {
+                        name: "Mix",
+                        kind: ParamKind::Float {
+                            min: 0.0,
+                            max: 1.0,
+                            step: 0.05,
+                        },
+                        desc: None,
+                    },
+                ],
+                StandardModule::Vocoder => &[
+                    ParamDef {
+                        name: "Car",
+                        kind: ParamKind::Input,
+                        desc: Some("Carrier signal (e.g. oscillator)"),
+                    },
+                    ParamDef {
+                        name: "Mod",
+                        kind: ParamKind::Input,
+                        desc: Some("Modulator signal to analyze"),
+                    },
+                    ParamDef {
+                        name: "Bands",
+                        kind: ParamKind::Int { min: 4, max: 32 },
+                        desc: Some("Number of filter bands"),
+                    },
+                    ParamDef {
+                        name: "Low",
+                        kind: ParamKind::Float {
+                            min: 50.0,
+                            max: 2000.0,
+                            step: 10.0,
+                        },
+                        desc: Some("Lowest band center (Hz)"),
+                    },
+                    ParamDef {
+                        name: "High",
+                        kind: ParamKind::Float {
+                            min: 1000.0,
+                            max: 16000.0,
+                            step: 100.0,
+                        },
+                        desc: Some("Highest band center (Hz)"),
+                    },
+                    ParamDef {
+                        name: "Atk",
+                        kind: ParamKind::Float {
+                            min: 0.001,
+                            max: 0.2,
+                            step: 0.001,
+                        },
+                        desc: Some("Envelope attack (s)"),
+                    },
+                    ParamDef {
+                        name: "Rel",
+                        kind: ParamKind::Float {
+                            min: 0.005,
+                            max: 1.0,
+                            step: 0.005,
+                        },
+                        desc: Some("Envelope release (s)"),
+                    },
+                    ParamDef {
+                        name: "Fmnt",
+                        kind: ParamKind::Float {
+                            min: -12.0,
+                            max: 12.0,
+                            step: 0.5,
+                        },
+                        desc: Some("Formant shift (semitones)"),
+                    },
+                ],
+                StandardModule::PitchShift => &[
+                    ParamDef {
+                        name: "In",
+                        kind: ParamKind::Float {
+                            min: -1.0,
+                            max: 1.0,
+                            step: 0.01,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Semi",
+                        kind: ParamKind::Float {
+                            min: -24.0,
+                            max: 24.0,
+                            step: 1.0,
+                        },
+                        desc: Some("Shift in semitones (degrees if Deg)"),
+                    },
+                    ParamDef {
+                        name: "Cent",
+                        kind: ParamKind::Float {
+                            min: -100.0,
+                            max: 100.0,
+                            step: 1.0,
+                        },
+                        desc: Some("Fine shift in cents"),
+                    },
+                    ParamDef {
+                        name: "Win",
+                        kind: ParamKind::Float {
+                            min: 0.01,
+                            max: 0.2,
+                            step: 0.005,
+                        },
+                        desc: Some("Window size (s)"),
+                    },
+                    ParamDef {
+                        name: "Mix",
+                        kind: ParamKind::Float {
+                            min: 0.0,
+                            max: 1.0,
+                            step: 0.05,
+                        },
+                        desc: None,
+                    },
+                    ParamDef {
+                        name: "Deg",
+                        kind: ParamKind::Toggle,
+                        desc: Some("Read Semi as scale degrees"),
+                    },
                 ],
                 StandardModule::Flanger => &[
                     ParamDef {
@@ -1755,6 +2149,11 @@ impl ModuleKind {
                         kind: ParamKind::Input,
                         desc: None,
                     },
+                    ParamDef {
+                        name: "Chan",
+                        kind: ParamKind::Enum,
+                        desc: Some("Stereo channel to play"),
+                    },
                 ],
                 StandardModule::Probe => &[ParamDef {
                     name: "In",
@@ -1799,6 +2198,9 @@ pub enum ModuleParams {
     DegreeGate {
         degree: i32,
     },
+    Lane {
+        name: String,
+    },
     Osc {
         wave: WaveType,
         freq: TimeValue,
@@ -1860,6 +2262,45 @@ pub enum ModuleParams {
         dist_type: DistType,
         drive: f32,
         asymmetry: f32,
+        #[serde(default)]
+        oversample: Oversample,
+        connected: u8,
+    },
+    Crush {
+        bits: f32,
+        rate: f32,
+        jitter: f32,
+        drive: f32,
+        mix: f32,
+        dither: bool,
+        anti_alias: bool,
+        connected: u8,
+    },
+    Ring {
+        freq: TimeValue,
+        mix: f32,
+        connected: u8,
+    },
+    FreqShift {
+        shift: f32,
+        mix: f32,
+        connected: u8,
+    },
+    Vocoder {
+        bands: i32,
+        low: f32,
+        high: f32,
+        attack: f32,
+        release: f32,
+        formant: f32,
+        connected: u8,
+    },
+    PitchShift {
+        semitones: f32,
+        cents: f32,
+        window: f32,
+        mix: f32,
+        degrees: bool,
         connected: u8,
     },
     Flanger {
@@ -1897,7 +2338,9 @@ pub enum ModuleParams {
         file_idx: usize,
         file_name: String,
         #[serde(skip)]
-        samples: std::sync::Arc<Vec<f32>>,
+        samples: std::sync::Arc<crate::sample::SampleBuffer>,
+        #[serde(default)]
+        channel: SampleChannel,
         connected: u8,
     },
     Probe {
@@ -1930,9 +2373,13 @@ impl ModuleParams {
                 },
             },
             ModuleKind::Standard(s) => match s {
-                StandardModule::Freq | StandardModule::Gate | StandardModule::Degree => {
-                    ModuleParams::None
-                }
+                StandardModule::Freq
+                | StandardModule::Gate
+                | StandardModule::Degree
+                | StandardModule::Velocity => ModuleParams::None,
+                StandardModule::Lane => ModuleParams::Lane {
+                    name: String::new(),
+                },
                 StandardModule::DegreeGate => ModuleParams::DegreeGate { degree: 0 },
                 StandardModule::Osc => ModuleParams::Osc {
                     wave: WaveType::Sin,
@@ -2007,6 +2454,44 @@ impl ModuleParams {
                     dist_type: DistType::Tube,
                     drive: 2.0,
                     asymmetry: 0.0,
+                    oversample: Oversample::X2,
+                    connected: 0xFF,
+                },
+                StandardModule::Crush => ModuleParams::Crush {
+                    bits: 8.0,
+                    rate: 1.0,
+                    jitter: 0.0,
+                    drive: 1.0,
+                    mix: 1.0,
+                    dither: false,
+                    anti_alias: false,
+                    connected: 0xFF,
+                },
+                StandardModule::Ring => ModuleParams::Ring {
+                    freq: TimeValue::from_hz(200.0),
+                    mix: 1.0,
+                    connected: 0xFF,
+                },
+                StandardModule::FreqShift => ModuleParams::FreqShift {
+                    shift: 100.0,
+                    mix: 1.0,
+                    connected: 0xFF,
+                },
+                StandardModule::Vocoder => ModuleParams::Vocoder {
+                    bands: 16,
+                    low: 100.0,
+                    high: 8000.0,
+                    attack: 0.005,
+                    release: 0.05,
+                    formant: 0.0,
+                    connected: 0xFF,
+                },
+                StandardModule::PitchShift => ModuleParams::PitchShift {
+                    semitones: 0.0,
+                    cents: 0.0,
+                    window: 0.05,
+                    mix: 1.0,
+                    degrees: false,
                     connected: 0xFF,
                 },
                 StandardModule::Flanger => ModuleParams::Flanger {
@@ -2044,7 +2529,8 @@ impl ModuleParams {
                 StandardModule::Sample => ModuleParams::Sample {
                     file_idx: 0,
                     file_name: String::new(),
-                    samples: std::sync::Arc::new(Vec::new()),
+                    samples: std::sync::Arc::default(),
+                    channel: SampleChannel::Mix,
                     connected: 0xFF,
                 },
                 StandardModule::Probe => ModuleParams::Probe { connected: 0xFF },
@@ -2058,7 +2544,9 @@ impl ModuleParams {
 
     pub fn connected(&self) -> u8 {
         match self {
-            ModuleParams::None | ModuleParams::DegreeGate { .. } => 0xFF,
+            ModuleParams::None | ModuleParams::DegreeGate { .. } | ModuleParams::Lane { .. } => {
+                0xFF
+            }
             ModuleParams::Osc { connected, .. } => *connected,
             ModuleParams::Rise { connected, .. } => *connected,
             ModuleParams::Fall { connected, .. } => *connected,
@@ -2071,6 +2559,11 @@ impl ModuleParams {
             ModuleParams::Delay { connected, .. } => *connected,
             ModuleParams::Reverb { connected, .. } => *connected,
             ModuleParams::Distortion { connected, .. } => *connected,
+            ModuleParams::Crush { connected, .. } => *connected,
+            ModuleParams::Ring { connected, .. } => *connected,
+            ModuleParams::FreqShift { connected, .. } => *connected,
+            ModuleParams::Vocoder { connected, .. } => *connected,
+            ModuleParams::PitchShift { connected, .. } => *connected,
             ModuleParams::Flanger { connected, .. } => *connected,
             ModuleParams::Mul { connected, .. } => *connected,
             ModuleParams::Add { connected, .. } => *connected,
@@ -2090,7 +2583,8 @@ impl ModuleParams {
         match self {
             ModuleParams::None
             | ModuleParams::SubPatch { .. }
-            | ModuleParams::DegreeGate { .. } => None,
+            | ModuleParams::DegreeGate { .. }
+            | ModuleParams::Lane { .. } => None,
             ModuleParams::Osc { connected, .. } => Some(connected),
             ModuleParams::Rise { connected, .. } => Some(connected),
             ModuleParams::Fall { connected, .. } => Some(connected),
@@ -2103,6 +2597,11 @@ impl ModuleParams {
             ModuleParams::Delay { connected, .. } => Some(connected),
             ModuleParams::Reverb { connected, .. } => Some(connected),
             ModuleParams::Distortion { connected, .. } => Some(connected),
+            ModuleParams::Crush { connected, .. } => Some(connected),
+            ModuleParams::Ring { connected, .. } => Some(connected),
+            ModuleParams::FreqShift { connected, .. } => Some(connected),
+            ModuleParams::Vocoder { connected, .. } => Some(connected),
+            ModuleParams::PitchShift { connected, .. } => Some(connected),
             ModuleParams::Flanger { connected, .. } => Some(connected),
             ModuleParams::Mul { connected, .. } => Some(connected),
             ModuleParams::Add { connected, .. } => Some(connected),
@@ -2184,6 +2683,58 @@ impl ModuleParams {
                 3 => Some(*asymmetry),
                 _ => None,
             },
+            ModuleParams::Crush {
+                bits,
+                rate,
+                jitter,
+                drive,
+                mix,
+                ..
+            } => match idx {
+                1 => Some(*bits),
+                2 => Some(*rate),
+                3 => Some(*jitter),
+                4 => Some(*drive),
+                5 => Some(*mix),
+                _ => None,
+            },
+            ModuleParams::Ring { mix, .. } => match idx {
+                2 => Some(*mix),
+                _ => None,
+            },
+            ModuleParams::FreqShift { shift, mix, .. } => match idx {
+                1 => Some(*shift),
+                2 => Some(*mix),
+                _ => None,
+            },
+            ModuleParams::Vocoder {
+                low,
+                high,
+                attack,
+                release,
+                formant,
+                ..
+            } => match idx {
+                3 => Some(*low),
+                4 => Some(*high),
+                5 => Some(*attack),
+                6 => Some(*release),
+                7 => Some(*formant),
+                _ => None,
+            },
+            ModuleParams::PitchShift {
+                semitones,
+                cents,
+                window,
+                mix,
+                ..
+            } => match idx {
+                1 => Some(*semitones),
+                2 => Some(*cents),
+                3 => Some(*window),
+                4 => Some(*mix),
+                _ => None,
+            },
             ModuleParams::Flanger {
                 rate,
                 depth,
@@ -2290,6 +2841,59 @@ impl ModuleParams {
                 3 => *asymmetry = val,
                 _ => {}
             },
+            ModuleParams::Crush {
+                bits,
+                rate,
+                jitter,
+                drive,
+                mix,
+                ..
+            } => match idx {
+                1 => *bits = val,
+                2 => *rate = val,
+                3 => *jitter = val,
+                4 => *drive = val,
+                5 => *mix = val,
+                _ => {}
+            },
+            ModuleParams::Ring { mix, .. } => {
+                if idx == 2 {
+                    *mix = val
+                }
+            }
+            ModuleParams::FreqShift { shift, mix, .. } => match idx {
+                1 => *shift = val,
+                2 => *mix = val,
+                _ => {}
+            },
+            ModuleParams::Vocoder {
+                low,
+                high,
+                attack,
+                release,
+                formant,
+                ..
+            } => match idx {
+                3 => *low = val,
+                4 => *high = val,
+                5 => *attack = val,
+                6 => *release = val,
+                7 => *formant = val,
+                _ => {}
+            },
+            ModuleParams::PitchShift {
+                semitones,
+                cents,
+                window,
+                mix,
+                ..
+            } => match idx {
+                1 => *semitones = val,
+                2 => *cents = val,
+                3 => *window = val,
+                4 => *mix = val,
+                _ => {}
+            },
             ModuleParams::Flanger {
                 rate,
                 depth,
@@ -2353,6 +2957,10 @@ impl ModuleParams {
 
     pub fn get_time(&self, idx: usize) -> Option<&TimeValue> {
         match self {
+            ModuleParams::Ring { freq, .. } => match idx {
+                1 => Some(freq),
+                _ => None,
+            },
             ModuleParams::Osc { freq, .. } => match idx {
                 1 => Some(freq),
                 _ => None,
@@ -2379,6 +2987,10 @@ impl ModuleParams {
 
     pub fn get_time_mut(&mut self, idx: usize) -> Option<&mut TimeValue> {
         match self {
+            ModuleParams::Ring { freq, .. } => match idx {
+                1 => Some(freq),
+                _ => None,
+            },
             ModuleParams::Osc { freq, .. } => match idx {
                 1 => Some(freq),
                 _ => None,
@@ -2421,6 +3033,10 @@ impl ModuleParams {
         match self {
             ModuleParams::Osc { wave, .. } if idx == 0 => *wave = wave.next(),
             ModuleParams::Distortion { dist_type, .. } if idx == 1 => *dist_type = dist_type.next(),
+            ModuleParams::Distortion { oversample, .. } if idx == 4 => {
+                *oversample = oversample.next()
+            }
+            ModuleParams::Sample { channel, .. } if idx == 2 => *channel = channel.next(),
             _ => {}
         }
     }
@@ -2429,6 +3045,10 @@ impl ModuleParams {
         match self {
             ModuleParams::Osc { wave, .. } if idx == 0 => *wave = wave.prev(),
             ModuleParams::Distortion { dist_type, .. } if idx == 1 => *dist_type = dist_type.prev(),
+            ModuleParams::Distortion { oversample, .. } if idx == 4 => {
+                *oversample = oversample.prev()
+            }
+            ModuleParams::Sample { channel, .. } if idx == 2 => *channel = channel.prev(),
             _ => {}
         }
     }
@@ -2439,15 +3059,28 @@ impl ModuleParams {
                 4 => *uni,
                 _ => false,
             },
+            ModuleParams::Crush {
+                dither, anti_alias, ..
+            } => match idx {
+                6 => *dither,
+                7 => *anti_alias,
+                _ => false,
+            },
+            ModuleParams::PitchShift { degrees, .. } => match idx {
+                5 => *degrees,
+                _ => false,
+            },
             _ => false,
         }
     }
 
     pub fn toggle(&mut self, idx: usize) {
-        if let ModuleParams::Osc { uni, .. } = self
-            && idx == 4
-        {
-            *uni = !*uni
+        match self {
+            ModuleParams::Osc { uni, .. } if idx == 4 => *uni = !*uni,
+            ModuleParams::Crush { dither, .. } if idx == 6 => *dither = !*dither,
+            ModuleParams::Crush { anti_alias, .. } if idx == 7 => *anti_alias = !*anti_alias,
+            ModuleParams::PitchShift { degrees, .. } if idx == 5 => *degrees = !*degrees,
+            _ => {}
         }
     }
 
@@ -2457,15 +3090,19 @@ impl ModuleParams {
                 0 => Some(*degree),
                 _ => None,
             },
+            ModuleParams::Vocoder { bands, .. } => match idx {
+                2 => Some(*bands),
+                _ => None,
+            },
             _ => None,
         }
     }
 
     pub fn set_int(&mut self, idx: usize, val: i32) {
-        if let ModuleParams::DegreeGate { degree } = self
-            && idx == 0
-        {
-            *degree = val
+        match self {
+            ModuleParams::DegreeGate { degree } if idx == 0 => *degree = val,
+            ModuleParams::Vocoder { bands, .. } if idx == 2 => *bands = val,
+            _ => {}
         }
     }
 
@@ -2477,6 +3114,8 @@ impl ModuleParams {
         match self {
             ModuleParams::Osc { wave, .. } if idx == 0 => Some(wave.name()),
             ModuleParams::Distortion { dist_type, .. } if idx == 1 => Some(dist_type.name()),
+            ModuleParams::Distortion { oversample, .. } if idx == 4 => Some(oversample.name()),
+            ModuleParams::Sample { channel, .. } if idx == 2 => Some(channel.name()),
             _ => None,
         }
     }
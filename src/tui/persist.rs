@@ -1,6 +1,8 @@
 use super::grid::GridPos;
 use super::module::{Module, ModuleKind, ModuleParams, Orientation, StandardModule, SubPatchId};
 use super::patch::{Patch, PatchSet, SubPatchDef};
+use crate::sample::SampleBuffer;
+use crate::scale::{Scale, cmin};
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -16,6 +18,8 @@ pub struct PatchFile {
     #[serde(default = "default_bars")]
     pub bars: f32,
     #[serde(default)]
+    pub scale: Option<String>,
+    #[serde(default, skip_serializing)]
     pub scale_idx: usize,
     #[serde(default)]
     pub modules: Vec<ModuleDef>,
@@ -23,6 +27,14 @@ pub struct PatchFile {
     pub track: Option<String>,
     #[serde(default)]
     pub subpatches: Vec<SubPatchFileDef>,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub tuning: Option<String>,
+    #[serde(default = "default_swing")]
+    pub swing: f32,
+    #[serde(default)]
+    pub groove: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -39,6 +51,9 @@ fn default_bpm() -> f32 {
 fn default_bars() -> f32 {
     1.0
 }
+fn default_swing() -> f32 {
+    50.0
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ModuleDef {
@@ -55,29 +70,13 @@ fn is_horizontal(o: &Orientation) -> bool {
     *o == Orientation::Horizontal
 }
 
-fn load_wav_samples(path: &str) -> Option<Arc<Vec<f32>>> {
-    let reader = hound::WavReader::open(path).ok()?;
-    let spec = reader.spec();
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Float => reader
-            .into_samples::<f32>()
-            .filter_map(|s| s.ok())
-            .step_by(spec.channels as usize)
-            .collect(),
-        hound::SampleFormat::Int => {
-            let max = (1 << (spec.bits_per_sample - 1)) as f32;
-            reader
-                .into_samples::<i32>()
-                .filter_map(|s| s.ok())
-                .step_by(spec.channels as usize)
-                .map(|s| s as f32 / max)
-                .collect()
-        }
-    };
-    Some(Arc::new(samples))
+/// Loads a WAV file resampled to the engine's `sample_rate`.
+pub fn load_wav_samples(path: &str, sample_rate: u32) -> Option<Arc<SampleBuffer>> {
+    let buffer = crate::wav::load_wav(path).ok()?;
+    Some(Arc::new(buffer.resample(sample_rate)))
 }
 
-fn reload_samples_in_patch(patch: &mut Patch) -> Vec<String> {
+fn reload_samples_in_patch(patch: &mut Patch, sample_rate: u32) -> Vec<String> {
     let mut missing = Vec::new();
     let ids: Vec<_> = patch.all_modules().map(|m| m.id).collect();
     for id in ids {
@@ -87,7 +86,7 @@ fn reload_samples_in_patch(patch: &mut Patch) -> Vec<String> {
             } = &mut m.params
             && !file_name.is_empty()
         {
-            if let Some(loaded) = load_wav_samples(file_name) {
+            if let Some(loaded) = load_wav_samples(file_name, sample_rate) {
                 *samples = loaded;
             } else {
                 missing.push(file_name.clone());
@@ -102,10 +101,15 @@ impl PatchFile {
         Self {
             bpm: 120.0,
             bars: 1.0,
+            scale: None,
             scale_idx: 0,
             modules: Vec::new(),
             track: None,
             subpatches: Vec::new(),
+            seed: 0,
+            tuning: None,
+            swing: 50.0,
+            groove: None,
         }
     }
 }
@@ -186,13 +190,13 @@ pub fn patchset_to_file(
     patches: &PatchSet,
     bpm: f32,
     bars: f32,
-    scale_idx: usize,
+    scale: Scale,
     track: Option<&str>,
 ) -> PatchFile {
     let mut pf = PatchFile::new();
     pf.bpm = bpm;
     pf.bars = bars;
-    pf.scale_idx = scale_idx;
+    pf.scale = Some(scale.to_string());
     pf.track = track.map(|s| s.to_string());
     pf.modules = patch_to_modules(patches.root());
 
@@ -208,23 +212,43 @@ pub fn patchset_to_file(
     pf
 }
 
+/// Files written before scales were stored by name only have `scale_idx`,
+/// an index into the old chromatic / C major / C minor / C# major ... list.
+fn file_scale(pf: &PatchFile) -> Scale {
+    if let Some(scale) = pf.scale.as_deref().and_then(|name| name.parse().ok()) {
+        return scale;
+    }
+    let (mode, root) = match pf.scale_idx {
+        0 => ("chromatic", 0),
+        idx if idx <= 24 => (if idx % 2 == 1 { "major" } else { "minor" }, (idx - 1) / 2),
+        _ => ("minor", 0),
+    };
+    let mut scale = Scale::mode(mode).unwrap_or_else(cmin);
+    scale.root(root as i32);
+    scale
+}
+
 pub struct LoadResult {
     pub patches: PatchSet,
     pub bpm: f32,
     pub bars: f32,
-    pub scale_idx: usize,
+    pub scale: Scale,
     pub track: Option<String>,
+    pub seed: u64,
+    pub tuning: Option<String>,
+    pub swing: f32,
+    pub groove: Option<String>,
     pub missing_samples: Vec<String>,
 }
 
-pub fn file_to_patchset(pf: &PatchFile) -> LoadResult {
+pub fn file_to_patchset(pf: &PatchFile, sample_rate: u32) -> LoadResult {
     let mut next_module_id = 0u32;
 
     const ROOT_WIDTH: u16 = 41;
     const ROOT_HEIGHT: u16 = 21;
 
     let mut root = modules_to_patch(&pf.modules, ROOT_WIDTH, ROOT_HEIGHT, &mut next_module_id);
-    let mut missing_samples = reload_samples_in_patch(&mut root);
+    let mut missing_samples = reload_samples_in_patch(&mut root, sample_rate);
 
     let mut patches = PatchSet::new(ROOT_WIDTH, ROOT_HEIGHT);
     patches.set_root(root);
@@ -235,7 +259,7 @@ pub fn file_to_patchset(pf: &PatchFile) -> LoadResult {
         let (r, g, b) = sub.color;
         let mut def = SubPatchDef::new(sub.name.clone(), Color::Rgb(r, g, b));
         def.patch = modules_to_patch(&sub.modules, ROOT_WIDTH, ROOT_HEIGHT, &mut next_module_id);
-        missing_samples.extend(reload_samples_in_patch(&mut def.patch));
+        missing_samples.extend(reload_samples_in_patch(&mut def.patch, sample_rate));
         patches.insert_subpatch(id, def);
     }
 
@@ -245,8 +269,12 @@ pub fn file_to_patchset(pf: &PatchFile) -> LoadResult {
         patches,
         bpm: pf.bpm,
         bars: pf.bars,
-        scale_idx: pf.scale_idx,
+        scale: file_scale(pf),
         track: pf.track.clone(),
+        seed: pf.seed,
+        tuning: pf.tuning.clone(),
+        swing: pf.swing,
+        groove: pf.groove.clone(),
         missing_samples,
     }
 }
@@ -256,10 +284,18 @@ pub fn save_patchset(
     patches: &PatchSet,
     bpm: f32,
     bars: f32,
-    scale_idx: usize,
+    scale: Scale,
     track: Option<&str>,
+    seed: u64,
+    tuning: Option<&str>,
+    swing: f32,
+    groove: Option<&str>,
 ) -> io::Result<()> {
-    let pf = patchset_to_file(patches, bpm, bars, scale_idx, track);
+    let mut pf = patchset_to_file(patches, bpm, bars, scale, track);
+    pf.seed = seed;
+    pf.tuning = tuning.map(|s| s.to_string());
+    pf.swing = swing;
+    pf.groove = groove.map(|s| s.to_string());
     let config = ron::ser::PrettyConfig::new()
         .depth_limit(4)
         .indentor("  ".to_string());
@@ -268,11 +304,11 @@ pub fn save_patchset(
     fs::write(path, content)
 }
 
-pub fn load_patchset(path: &Path) -> io::Result<LoadResult> {
+pub fn load_patchset(path: &Path, sample_rate: u32) -> io::Result<LoadResult> {
     let content = fs::read_to_string(path)?;
     let pf: PatchFile =
         ron::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    Ok(file_to_patchset(&pf))
+    Ok(file_to_patchset(&pf, sample_rate))
 }
 
 #[cfg(test)]
@@ -313,7 +349,7 @@ mod tests {
             };
         }
 
-        let pf = patchset_to_file(&patches, 120.0, 1.0, 0, Some("C4 D4 E4"));
+        let pf = patchset_to_file(&patches, 120.0, 1.0, cmin(), Some("C4 D4 E4"));
         let config = ron::ser::PrettyConfig::new()
             .depth_limit(4)
             .indentor("  ".to_string());
@@ -383,7 +419,13 @@ mod tests {
             }
         }
 
-        let pf = patchset_to_file(&patches, 90.0, 4.0, 5, Some("C4 E4 G4\n# comment\nD4"));
+        let pf = patchset_to_file(
+            &patches,
+            90.0,
+            4.0,
+            "D dorian".parse().unwrap(),
+            Some("C4 E4 G4\n# comment\nD4"),
+        );
 
         let serialized =
             ron::ser::to_string_pretty(&pf, ron::ser::PrettyConfig::default()).unwrap();
@@ -391,12 +433,12 @@ mod tests {
 
         assert!((pf2.bpm - 90.0).abs() < 0.01);
         assert!((pf2.bars - 4.0).abs() < 0.01);
-        assert_eq!(pf2.scale_idx, 5);
+        assert_eq!(pf2.scale.as_deref(), Some("D dorian"));
         assert_eq!(pf2.modules.len(), 4);
         assert!(pf2.track.as_ref().unwrap().contains("# comment"));
 
-        let result = file_to_patchset(&pf2);
-        assert_eq!(result.scale_idx, 5);
+        let result = file_to_patchset(&pf2, 44100);
+        assert_eq!(result.scale.to_string(), "D dorian");
 
         assert!((result.bpm - 90.0).abs() < 0.01);
         assert!((result.bars - 4.0).abs() < 0.01);
@@ -437,6 +479,31 @@ mod tests {
         assert!(env2.params.env_points().unwrap()[1].curve);
     }
 
+    #[test]
+    fn test_legacy_scale_index() {
+        let pf: PatchFile = ron::from_str("(bpm: 120.0, scale_idx: 6)").unwrap();
+        assert_eq!(file_to_patchset(&pf, 44100).scale, crate::scale::dmin());
+
+        let pf: PatchFile = ron::from_str("(scale: Some(\"F# blues\"), scale_idx: 6)").unwrap();
+        assert_eq!(file_to_patchset(&pf, 44100).scale.to_string(), "F# blues");
+    }
+
+    #[test]
+    fn test_groove_settings() {
+        let pf: PatchFile = ron::from_str("(bpm: 120.0)").unwrap();
+        let result = file_to_patchset(&pf, 44100);
+        assert_eq!(result.swing, 50.0);
+        assert_eq!(result.groove, None);
+
+        let mut pf = PatchFile::new();
+        pf.swing = 62.0;
+        pf.groove = Some("Laid Back".to_string());
+        let text = ron::to_string(&pf).unwrap();
+        let result = file_to_patchset(&ron::from_str(&text).unwrap(), 44100);
+        assert_eq!(result.swing, 62.0);
+        assert_eq!(result.groove.as_deref(), Some("Laid Back"));
+    }
+
     #[test]
     fn test_subpatch_serialization() {
         use crate::tui::module::{RoutingModule, SubpatchModule};
@@ -490,13 +557,13 @@ mod tests {
             GridPos::new(5, 0),
         );
 
-        let pf = patchset_to_file(&patches, 120.0, 1.0, 0, None);
+        let pf = patchset_to_file(&patches, 120.0, 1.0, cmin(), None);
 
         let serialized =
             ron::ser::to_string_pretty(&pf, ron::ser::PrettyConfig::default()).unwrap();
 
         let pf2: PatchFile = ron::from_str(&serialized).unwrap();
-        let result = file_to_patchset(&pf2);
+        let result = file_to_patchset(&pf2, 44100);
 
         assert_eq!(result.patches.root().all_modules().count(), 4);
         assert_eq!(result.patches.subpatch_count(), 1);
@@ -114,6 +114,12 @@ impl Widget for EditWidget<'_> {
                                 .unwrap_or("?")
                                 .to_string()
                         }
+                    } else if let ModuleParams::Lane { name } = &self.module.params {
+                        if name.is_empty() {
+                            "(no lane)".to_string()
+                        } else {
+                            name.clone()
+                        }
                     } else if self.module.kind == ModuleKind::Standard(StandardModule::Sample)
                         && i == 0
                     {
@@ -220,14 +226,24 @@ impl Widget for EditWidget<'_> {
             && let ModuleParams::Sample { samples, .. } = &self.module.params
             && !samples.is_empty()
         {
-            let sample_count = samples.len();
-            let duration = sample_count as f32 / 44100.0;
             y += 1;
             set_str(
                 buf,
                 area.x + 2,
                 y,
-                &format!("{} samp ({:.2}s)", sample_count, duration),
+                &format!("{} samp ({:.2}s)", samples.len(), samples.duration()),
+                label_style,
+            );
+            y += 1;
+            set_str(
+                buf,
+                area.x + 2,
+                y,
+                &format!(
+                    "src {}Hz {}ch",
+                    samples.source_rate(),
+                    samples.source_channels()
+                ),
                 label_style,
             );
         }
@@ -9,6 +9,7 @@ mod palette;
 mod probe;
 mod sample;
 mod status;
+mod track_errors;
 
 pub use util::{set_cell, set_str};
 
@@ -21,6 +22,7 @@ pub use palette::PaletteWidget;
 pub use probe::ProbeWidget;
 pub use sample::SampleWidget;
 pub use status::StatusWidget;
+pub use track_errors::TrackErrorsWidget;
 
 use ratatui::{
     buffer::Buffer,
@@ -1,4 +1,5 @@
 use super::util::{set_cell, set_str};
+use crate::TimeSignature;
 use crate::tui::grid::GridPos;
 use ratatui::{
     buffer::Buffer,
@@ -13,6 +14,7 @@ pub struct StatusWidget<'a> {
     message: Option<&'a str>,
     playing: bool,
     output_history: &'a [f32],
+    tempo: Option<(f32, &'a [TimeSignature])>,
 }
 
 impl<'a> StatusWidget<'a> {
@@ -23,6 +25,7 @@ impl<'a> StatusWidget<'a> {
             message: None,
             playing: false,
             output_history: &[],
+            tempo: None,
         }
     }
 
@@ -40,6 +43,12 @@ impl<'a> StatusWidget<'a> {
         self.output_history = history;
         self
     }
+
+    /// Shows the tempo and the time signatures of the track's bars.
+    pub fn tempo(mut self, bpm: f32, meters: &'a [TimeSignature]) -> Self {
+        self.tempo = Some((bpm, meters));
+        self
+    }
 }
 
 impl Widget for StatusWidget<'_> {
@@ -70,6 +79,18 @@ impl Widget for StatusWidget<'_> {
             wave_start += 2;
         }
 
+        if let Some((bpm, meters)) = self.tempo {
+            // Each meter once, in the order the bars first use it.
+            let mut tempo = format!("{:.0}bpm", bpm);
+            for (i, meter) in meters.iter().enumerate() {
+                if !meters[..i].contains(meter) {
+                    tempo.push_str(&format!(" {}", meter));
+                }
+            }
+            set_str(buf, wave_start, area.y, &tempo, pos_style);
+            wave_start += tempo.len() as u16 + 1;
+        }
+
         let msg_len = self.message.map(|m| m.len() as u16 + 2).unwrap_or(0);
         let wave_end = area.x + area.width.saturating_sub(msg_len);
 
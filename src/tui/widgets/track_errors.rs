@@ -0,0 +1,71 @@
+use super::util::{set_cell, set_str};
+use crate::track::TrackParseError;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+
+/// Lists track parse errors, each followed by its source line with the
+/// offending span highlighted.
+pub struct TrackErrorsWidget<'a> {
+    text: &'a str,
+    errors: &'a [TrackParseError],
+}
+
+impl<'a> TrackErrorsWidget<'a> {
+    pub fn new(text: &'a str, errors: &'a [TrackParseError]) -> Self {
+        Self { text, errors }
+    }
+
+    pub fn height(errors: &[TrackParseError]) -> u16 {
+        errors.len() as u16 * 2
+    }
+}
+
+impl Widget for TrackErrorsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let msg_style = Style::default().fg(Color::Red);
+        let text_style = Style::default().fg(Color::Gray);
+        let mark_style = Style::default()
+            .fg(Color::White)
+            .bg(Color::Red)
+            .add_modifier(Modifier::BOLD);
+        let width = area.width as usize;
+
+        for (i, error) in self.errors.iter().enumerate() {
+            let y = area.y + i as u16 * 2;
+            if y + 1 >= area.y + area.height {
+                break;
+            }
+            let msg: String = error.to_string().chars().take(width).collect();
+            set_str(buf, area.x, y, &msg, msg_style);
+
+            let line_start = match error.line {
+                0 | 1 => 0,
+                line => self
+                    .text
+                    .match_indices('\n')
+                    .nth(line - 2)
+                    .map_or(self.text.len(), |(idx, _)| idx + 1),
+            };
+            let line = self.text[line_start..].lines().next().unwrap_or("");
+            // Scroll long lines so the highlight stays in view.
+            let skip = (error.column - 1).saturating_sub(width / 2);
+            for (col, (idx, ch)) in line.char_indices().skip(skip).take(width).enumerate() {
+                let offset = line_start + idx;
+                let marked = error.span.contains(&offset)
+                    || (error.span.is_empty() && offset == error.span.start);
+                let style = if marked { mark_style } else { text_style };
+                let ch = if ch == '\t' { ' ' } else { ch };
+                set_cell(buf, area.x + col as u16, y + 1, ch, style);
+            }
+            let shown = line.chars().count().saturating_sub(skip);
+            if error.span.is_empty() && error.span.start >= line_start + line.len() && shown < width
+            {
+                set_cell(buf, area.x + shown as u16, y + 1, ' ', mark_style);
+            }
+        }
+    }
+}
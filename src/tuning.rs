@@ -0,0 +1,250 @@
+use std::fs;
+use std::path::Path;
+
+/// Maps note numbers to frequencies. The scale is a list of pitches in cents
+/// above the root whose last entry is the period; the keyboard mapping follows
+/// the Scala `.kbm` layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tuning {
+    description: String,
+    steps: Vec<f64>,
+    map: Vec<Option<i32>>,
+    first_note: i32,
+    last_note: i32,
+    middle_note: i32,
+    reference_note: i32,
+    reference_freq: f64,
+    octave_degree: i32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::edo(12)
+    }
+}
+
+impl Tuning {
+    pub fn edo(divisions: u32) -> Self {
+        let divisions = divisions.max(1);
+        Self {
+            description: format!("{}-EDO", divisions),
+            steps: (1..=divisions)
+                .map(|i| i as f64 * 1200.0 / divisions as f64)
+                .collect(),
+            ..Self::linear()
+        }
+    }
+
+    fn linear() -> Self {
+        Self {
+            description: String::new(),
+            steps: vec![1200.0],
+            map: Vec::new(),
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: 69,
+            reference_freq: 440.0,
+            octave_degree: 0,
+        }
+    }
+
+    pub fn from_scl(text: &str) -> Result<Self, String> {
+        let mut lines = content_lines(text);
+        let description = lines.next().ok_or("missing description")?.to_string();
+        let count: usize = lines
+            .next()
+            .ok_or("missing note count")?
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .parse()
+            .map_err(|_| "invalid note count")?;
+
+        let steps = lines
+            .take(count)
+            .map(parse_pitch)
+            .collect::<Result<Vec<_>, _>>()?;
+        if steps.len() != count {
+            return Err(format!("expected {} pitches, found {}", count, steps.len()));
+        }
+        if steps.is_empty() {
+            return Err("scale has no pitches".into());
+        }
+
+        Ok(Self {
+            description,
+            steps,
+            ..Self::linear()
+        })
+    }
+
+    /// Applies a Scala keyboard mapping. A map size of 0 maps every key
+    /// linearly onto consecutive scale degrees.
+    pub fn with_kbm(mut self, text: &str) -> Result<Self, String> {
+        let mut lines = content_lines(text);
+        let mut field = |name: &str| -> Result<&str, String> {
+            lines
+                .next()
+                .and_then(|l| l.split_whitespace().next())
+                .ok_or_else(|| format!("missing {}", name))
+        };
+        let int = |s: &str, name: &str| s.parse::<i32>().map_err(|_| format!("invalid {}", name));
+
+        let map_size = int(field("map size")?, "map size")?.max(0) as usize;
+        self.first_note = int(field("first note")?, "first note")?;
+        self.last_note = int(field("last note")?, "last note")?;
+        self.middle_note = int(field("middle note")?, "middle note")?;
+        self.reference_note = int(field("reference note")?, "reference note")?;
+        self.reference_freq = field("reference frequency")?
+            .parse()
+            .map_err(|_| "invalid reference frequency")?;
+        self.octave_degree = int(field("octave degree")?, "octave degree")?;
+
+        self.map = (0..map_size)
+            .map(|_| match field("mapping entry") {
+                Ok("x") | Err(_) => Ok(None),
+                Ok(s) => int(s, "mapping entry").map(Some),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(self)
+    }
+
+    pub fn load_scl(path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_scl(&text)
+    }
+
+    pub fn load_kbm(self, path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        self.with_kbm(&text)
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns 0.0 for keys the mapping leaves unmapped.
+    pub fn frequency(&self, note: i32) -> f32 {
+        if note < self.first_note || note > self.last_note {
+            return 0.0;
+        }
+        let Some(degree) = self.degree(note) else {
+            return 0.0;
+        };
+        let reference = self
+            .degree(self.reference_note)
+            .unwrap_or(self.reference_note - self.middle_note);
+        let cents = self.cents(degree) - self.cents(reference);
+        (self.reference_freq * 2.0f64.powf(cents / 1200.0)) as f32
+    }
+
+    fn degree(&self, note: i32) -> Option<i32> {
+        let offset = note - self.middle_note;
+        if self.map.is_empty() {
+            return Some(offset);
+        }
+        let size = self.map.len() as i32;
+        let octave_degree = if self.octave_degree > 0 {
+            self.octave_degree
+        } else {
+            self.steps.len() as i32
+        };
+        let mapped = self.map[offset.rem_euclid(size) as usize]?;
+        Some(offset.div_euclid(size) * octave_degree + mapped)
+    }
+
+    fn cents(&self, degree: i32) -> f64 {
+        let count = self.steps.len() as i32;
+        let period = self.steps[self.steps.len() - 1];
+        let step = degree.rem_euclid(count);
+        let base = if step == 0 {
+            0.0
+        } else {
+            self.steps[step as usize - 1]
+        };
+        degree.div_euclid(count) as f64 * period + base
+    }
+}
+
+fn content_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.starts_with('!'))
+}
+
+fn parse_pitch(line: &str) -> Result<f64, String> {
+    let token = line.split_whitespace().next().unwrap_or("");
+    let invalid = || format!("invalid pitch '{}'", token);
+    if token.contains('.') {
+        return token.parse().map_err(|_| invalid());
+    }
+    let (num, den) = token.split_once('/').unwrap_or((token, "1"));
+    let num: f64 = num.parse().map_err(|_| invalid())?;
+    let den: f64 = den.parse().map_err(|_| invalid())?;
+    if num <= 0.0 || den <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(1200.0 * (num / den).log2())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi_to_freq;
+
+    #[test]
+    fn test_default_matches_twelve_tet() {
+        let tuning = Tuning::default();
+        for note in 0..128 {
+            let expected = midi_to_freq(note as f32);
+            assert!((tuning.frequency(note) - expected).abs() < expected * 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_edo_step_size() {
+        let tuning = Tuning::edo(19);
+        let ratio = tuning.frequency(70) / tuning.frequency(69);
+        assert!((ratio - 2.0f32.powf(1.0 / 19.0)).abs() < 1e-5);
+        assert!((tuning.frequency(69 + 19) - 880.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_parse_scl() {
+        let scl = "! just.scl\n!\nJust major\n 7\n!\n9/8\n5/4\n4/3\n3/2\n5/3\n15/8\n2/1\n";
+        let tuning = Tuning::from_scl(scl).unwrap();
+        assert_eq!(tuning.description(), "Just major");
+        assert_eq!(tuning.len(), 7);
+
+        let c = tuning.frequency(60);
+        assert!((tuning.frequency(64) / c - 1.5).abs() < 1e-5);
+        assert!((tuning.frequency(67) / c - 2.0).abs() < 1e-5);
+
+        assert!(Tuning::from_scl("Broken\n2\n100.0\n").is_err());
+        assert!(Tuning::from_scl("Broken\n1\n-3/2\n").is_err());
+    }
+
+    #[test]
+    fn test_kbm_mapping() {
+        let scl = "Pentatonic\n5\n200.0\n400.0\n700.0\n900.0\n2/1\n";
+        let kbm = "! skip black keys\n12\n0\n127\n60\n69\n440.0\n5\n\
+                   0\nx\n1\nx\n2\nx\nx\n3\nx\n4\nx\nx\n";
+        let tuning = Tuning::from_scl(scl).unwrap().with_kbm(kbm).unwrap();
+
+        assert!((tuning.frequency(69) - 440.0).abs() < 1e-3);
+        assert_eq!(tuning.frequency(61), 0.0);
+        let c = tuning.frequency(60);
+        assert!((tuning.frequency(72) / c - 2.0).abs() < 1e-5);
+        assert!((tuning.frequency(67) / c - 2.0f32.powf(700.0 / 1200.0)).abs() < 1e-5);
+    }
+}
@@ -11,3 +11,37 @@ pub fn midi_to_freq(note: f32) -> f32 {
 pub fn semitones_to_hz(semitones: f32) -> f32 {
     440.0 * 2.0_f32.powf(semitones / 12.0)
 }
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+/// Derives an independent 32-bit stream seed from a render seed and a salt
+/// (module id, voice index, ...) using the splitmix64 finalizer.
+pub fn derive_seed(seed: u64, salt: u64) -> u32 {
+    let mut z = seed
+        .wrapping_add(salt.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)) as u32
+}
+
+pub(crate) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+pub(crate) fn next_random(state: &mut u32) -> f32 {
+    *state = state.wrapping_mul(196314165).wrapping_add(907633515);
+    *state as f32 / u32::MAX as f32
+}
+
+const DENORMAL_THRESHOLD: f32 = 1e-15;
+
+pub(crate) fn flush_denormal(sample: f32) -> f32 {
+    if sample.abs() < DENORMAL_THRESHOLD {
+        0.0
+    } else {
+        sample
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{filters::BandpassFilter, signal::Signal};
+
+pub const MAX_VOCODER_BANDS: usize = 32;
+
+pub struct Vocoder {
+    bands: usize,
+    low: f32,
+    high: f32,
+    attack: f32,
+    release: f32,
+    formant: f32,
+    modulator_filters: Vec<BandpassFilter>,
+    carrier_filters: Vec<BandpassFilter>,
+    envelopes: Vec<f32>,
+    attack_coeff: f32,
+    release_coeff: f32,
+    sample_rate: usize,
+    dirty: bool,
+}
+
+impl Default for Vocoder {
+    fn default() -> Self {
+        Self {
+            bands: 16,
+            low: 100.0,
+            high: 8000.0,
+            attack: 0.005,
+            release: 0.05,
+            formant: 0.0,
+            modulator_filters: (0..MAX_VOCODER_BANDS)
+                .map(|_| BandpassFilter::default())
+                .collect(),
+            carrier_filters: (0..MAX_VOCODER_BANDS)
+                .map(|_| BandpassFilter::default())
+                .collect(),
+            envelopes: vec![0.0; MAX_VOCODER_BANDS],
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            sample_rate: 0,
+            dirty: true,
+        }
+    }
+}
+
+impl Vocoder {
+    pub fn bands(&mut self, count: usize) -> &mut Self {
+        let count = count.clamp(1, MAX_VOCODER_BANDS);
+        if count != self.bands {
+            self.bands = count;
+            self.dirty = true;
+        }
+        self
+    }
+
+    pub fn range(&mut self, low_hz: f32, high_hz: f32) -> &mut Self {
+        // `low` stays far enough under 20k to leave room for the top band,
+        // and NaN falls back to the default range.
+        let low = if low_hz.is_nan() { 100.0 } else { low_hz };
+        let low = low.clamp(20.0, 20000.0 / 1.01);
+        let high = high_hz.max(low * 1.01).min(20000.0);
+        if low != self.low || high != self.high {
+            self.low = low;
+            self.high = high;
+            self.dirty = true;
+        }
+        self
+    }
+
+    pub fn attack(&mut self, seconds: f32) -> &mut Self {
+        let attack = seconds.max(0.0001);
+        if attack != self.attack {
+            self.attack = attack;
+            self.dirty = true;
+        }
+        self
+    }
+
+    pub fn release(&mut self, seconds: f32) -> &mut Self {
+        let release = seconds.max(0.0001);
+        if release != self.release {
+            self.release = release;
+            self.dirty = true;
+        }
+        self
+    }
+
+    pub fn formant(&mut self, semitones: f32) -> &mut Self {
+        let formant = semitones.clamp(-24.0, 24.0);
+        if formant != self.formant {
+            self.formant = formant;
+            self.dirty = true;
+        }
+        self
+    }
+
+    fn update_bands(&mut self) {
+        let sr = self.sample_rate as f32;
+        let nyquist = sr / 2.0;
+        let ratio = (self.high / self.low).powf(1.0 / self.bands as f32);
+        let q = ratio.sqrt() / (ratio - 1.0);
+        let formant_ratio = 2.0f32.powf(self.formant / 12.0);
+
+        for band in 0..self.bands {
+            let center = self.low * ratio.powf(band as f32 + 0.5);
+            self.modulator_filters[band].freq(center / nyquist).q(q);
+            self.carrier_filters[band]
+                .freq(center * formant_ratio / nyquist)
+                .q(q);
+        }
+
+        self.attack_coeff = (-1.0 / (self.attack * sr)).exp();
+        self.release_coeff = (-1.0 / (self.release * sr)).exp();
+        self.dirty = false;
+    }
+
+    pub fn output(&mut self, carrier: f32, modulator: f32, signal: &mut Signal) -> f32 {
+        if self.dirty || self.sample_rate != signal.sample_rate {
+            self.sample_rate = signal.sample_rate;
+            self.update_bands();
+        }
+
+        let mut sum = 0.0;
+        for band in 0..self.bands {
+            let level = self.modulator_filters[band].process(modulator).abs();
+            let env = &mut self.envelopes[band];
+            let coeff = if level > *env {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            *env = coeff * *env + (1.0 - coeff) * level;
+
+            sum += self.carrier_filters[band].process(carrier) * *env;
+        }
+
+        // The envelopes follow the rectified mean, 2/π of a sine's peak, so
+        // this brings a modulator matching the carrier back to unity gain.
+        // The bands are constant-Q, so the level doesn't depend on their
+        // count.
+        sum * std::f32::consts::FRAC_PI_2
+    }
+
+    pub fn reset(&mut self) {
+        for filter in self
+            .modulator_filters
+            .iter_mut()
+            .chain(self.carrier_filters.iter_mut())
+        {
+            filter.reset();
+        }
+        self.envelopes.fill(0.0);
+    }
+
+    pub fn copy_state_from(&mut self, other: &Vocoder) {
+        for (s, o) in self
+            .modulator_filters
+            .iter_mut()
+            .zip(&other.modulator_filters)
+        {
+            s.copy_state_from(o);
+        }
+        for (s, o) in self.carrier_filters.iter_mut().zip(&other.carrier_filters) {
+            s.copy_state_from(o);
+        }
+        self.envelopes.copy_from_slice(&other.envelopes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saw(i: usize, freq: f32) -> f32 {
+        (i as f32 * freq / 44100.0).fract() * 2.0 - 1.0
+    }
+
+    fn sine(i: usize, freq: f32) -> f32 {
+        (i as f32 * freq * std::f32::consts::TAU / 44100.0).sin()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_silent_modulator_silences_carrier() {
+        let mut vocoder = Vocoder::default();
+        let mut signal = Signal::new(44100);
+
+        let output: Vec<f32> = (0..4410)
+            .map(|i| vocoder.output(saw(i, 110.0), 0.0, &mut signal))
+            .collect();
+        assert!(rms(&output) < 1e-6);
+    }
+
+    #[test]
+    fn test_modulator_opens_matching_bands() {
+        let mut signal = Signal::new(44100);
+
+        let mut low = Vocoder::default();
+        let low_out: Vec<f32> = (0..8820)
+            .map(|i| low.output(sine(i, 300.0), sine(i, 300.0), &mut signal))
+            .collect();
+
+        let mut high = Vocoder::default();
+        let high_out: Vec<f32> = (0..8820)
+            .map(|i| high.output(sine(i, 300.0), sine(i, 5000.0), &mut signal))
+            .collect();
+
+        assert!(rms(&low_out[4410..]) > 0.01);
+        assert!(rms(&high_out[4410..]) < rms(&low_out[4410..]) * 0.1);
+    }
+
+    #[test]
+    fn test_output_stays_finite_with_formant_shift() {
+        let mut vocoder = Vocoder::default();
+        vocoder.bands(32).range(50.0, 12000.0).formant(12.0);
+        let mut signal = Signal::new(44100);
+
+        for i in 0..4410 {
+            let out = vocoder.output(saw(i, 220.0), saw(i, 97.0), &mut signal);
+            assert!(out.is_finite() && out.abs() < 4.0, "output {}", out);
+        }
+    }
+
+    #[test]
+    fn test_matched_modulator_keeps_carrier_level() {
+        for bands in [4, 16, 32] {
+            let mut vocoder = Vocoder::default();
+            vocoder.bands(bands);
+            let mut signal = Signal::new(44100);
+            let output: Vec<f32> = (0..44100)
+                .map(|i| vocoder.output(sine(i, 1000.0), sine(i, 1000.0), &mut signal))
+                .collect();
+            let level = rms(&output[22050..]) / std::f32::consts::FRAC_1_SQRT_2;
+            assert!((0.7..2.0).contains(&level), "{} bands: {}", bands, level);
+        }
+    }
+
+    #[test]
+    fn test_range_accepts_any_low() {
+        let mut vocoder = Vocoder::default();
+        let mut signal = Signal::new(44100);
+        for low in [20000.0, f32::NAN, f32::INFINITY, -5.0] {
+            vocoder.range(low, 8000.0);
+            assert!(vocoder.low < vocoder.high && vocoder.high <= 20000.0);
+            let out = vocoder.output(saw(1, 220.0), saw(1, 97.0), &mut signal);
+            assert!(out.is_finite());
+        }
+        vocoder.range(f32::NAN, f32::NAN);
+        assert_eq!(vocoder.low, 100.0);
+    }
+}
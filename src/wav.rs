@@ -1,4 +1,4 @@
-use crate::Signal;
+use crate::{SampleBuffer, Signal};
 
 pub fn save_wav(
     filename: &str,
@@ -18,7 +18,9 @@ pub fn save_wav(
     let total_samples = (duration_seconds * sample_rate as f32) as usize;
 
     for _ in 0..total_samples {
-        let sample = synth(&mut signal).clamp(-1., 1.);
+        let sample = synth(&mut signal);
+        signal.add_sample(sample);
+        let sample = signal.mix().clamp(-1., 1.);
         writer.write_sample(sample)?; // Left channel
         writer.write_sample(sample)?; // Right channel
         signal.advance();
@@ -27,3 +29,27 @@ pub fn save_wav(
     writer.finalize()?;
     Ok(())
 }
+
+pub fn load_wav(filename: &str) -> Result<SampleBuffer, Box<dyn std::error::Error>> {
+    let reader = hound::WavReader::open(filename)?;
+    let spec = reader.spec();
+    let data: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .filter_map(Result::ok)
+            .collect(),
+        hound::SampleFormat::Int => {
+            let max = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .filter_map(Result::ok)
+                .map(|s| s as f32 / max)
+                .collect()
+        }
+    };
+    Ok(SampleBuffer::from_interleaved(
+        &data,
+        spec.channels as usize,
+        spec.sample_rate,
+    ))
+}